@@ -1,5 +1,7 @@
 pub mod reader;
 pub mod preprocessing;
+pub mod separation;
 
-pub use reader::AudioReader;
-pub use preprocessing::AudioPreprocessor;
\ No newline at end of file
+pub use reader::{AudioReader, DownmixStrategy, ReadOptions};
+pub use preprocessing::{AudioPreprocessor, ANALYSIS_SAMPLE_RATE};
+pub use separation::SeparationConfig;
@@ -1,3 +1,17 @@
+use std::f64::consts::PI;
+
+use super::separation::{self, SeparationConfig, Spectrogram};
+
+/// Sample rate all feature extractors run at by default, so that frame
+/// durations and frequency resolutions are comparable between recordings
+pub const ANALYSIS_SAMPLE_RATE: u32 = 22050;
+
+/// Zero crossings of the sinc kernel on each side of the centre tap
+const SINC_ZERO_CROSSINGS: usize = 32;
+
+/// Above this many phases the kernel is evaluated per sample instead of tabulated
+const MAX_POLYPHASE_PHASES: usize = 4096;
+
 pub struct AudioPreprocessor;
 
 impl AudioPreprocessor {
@@ -21,4 +35,130 @@ impl AudioPreprocessor {
         let mean = samples.iter().sum::<f32>() / samples.len() as f32;
         samples.iter().map(|x| x - mean).collect()
     }
-}
\ No newline at end of file
+
+    /// Harmonic part of a mix, with percussion such as tabla strokes removed.
+    ///
+    /// Median filtering the spectrogram along time keeps sustained partials
+    /// and along frequency keeps broadband strokes; a soft mask built from the
+    /// two keeps the former.
+    pub fn harmonic(samples: &[f32], config: &SeparationConfig) -> Vec<f32> {
+        let spectrogram = Spectrogram::new(samples, config);
+        spectrogram.resynthesize(|block| separation::harmonic_block(&spectrogram, block, config))
+    }
+
+    /// The lead melody of a mix: its harmonic part with the drone subtracted
+    /// (see `harmonic` and `remove_drone`)
+    pub fn lead(samples: &[f32], sample_rate: u32, sa: f32, config: &SeparationConfig) -> Vec<f32> {
+        Self::remove_drone(&Self::harmonic(samples, config), sample_rate, sa, config)
+    }
+
+    /// The lead melody from the harmonic part of a mix.
+    ///
+    /// The drone is taken to be whatever sound persists on the harmonics of
+    /// `sa` (in Hz) and of the configured drone swara, and its sustained level
+    /// is subtracted there, so pauses in the melody fall silent instead of
+    /// handing the pitch tracker the tanpura. That level is measured over the
+    /// whole recording before any of it is taken off, so the spectrogram is
+    /// taken twice rather than kept; neither pass needs the median filters.
+    pub fn remove_drone(harmonic: &[f32], sample_rate: u32, sa: f32, config: &SeparationConfig) -> Vec<f32> {
+        let spectrogram = Spectrogram::new(harmonic, config);
+        let on_drone = separation::drone_bins(sample_rate, sa, config);
+        let sustained = separation::sustained_levels(&spectrogram, config);
+
+        spectrogram.resynthesize(|block| {
+            let mut spectra = spectrogram.spectra(block);
+            for frame in &mut spectra {
+                for (bin, value) in frame.iter_mut().enumerate() {
+                    if on_drone[bin] {
+                        *value *= separation::drone_gain(value.norm(), sustained[bin], config);
+                    }
+                }
+            }
+            spectra
+        })
+    }
+
+    /// Band-limited resampling of a mono signal from `from_rate` to `to_rate` Hz.
+    ///
+    /// Uses a Blackman-windowed sinc interpolator arranged as a polyphase
+    /// filter bank. When downsampling the cutoff is lowered to the new Nyquist
+    /// frequency so that content above it is removed rather than aliased.
+    pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+        if from_rate == to_rate || from_rate == 0 || to_rate == 0 || samples.is_empty() {
+            return samples.to_vec();
+        }
+
+        // Reduce the ratio so that output sample i reads input position i * step / phases
+        let divisor = gcd(from_rate as u64, to_rate as u64);
+        let phases = (to_rate as u64 / divisor) as usize;
+        let step = (from_rate as u64 / divisor) as usize;
+
+        // Cutoff relative to the input Nyquist frequency
+        let cutoff = (to_rate as f64 / from_rate as f64).min(1.0);
+        let half_width = (SINC_ZERO_CROSSINGS as f64 / cutoff).ceil() as usize;
+        let taps = 2 * half_width;
+
+        let kernel = |offset: f64| -> f32 {
+            // `offset` is the distance in input samples from the interpolated position
+            let x = offset / half_width as f64;
+            if x.abs() >= 1.0 {
+                return 0.0;
+            }
+            let window = 0.42 + 0.5 * (PI * x).cos() + 0.08 * (2.0 * PI * x).cos();
+            (cutoff * sinc(cutoff * offset) * window) as f32
+        };
+
+        // Tap k of phase p weights input sample (base + k + 1 - half_width)
+        let table: Option<Vec<Vec<f32>>> = (phases <= MAX_POLYPHASE_PHASES).then(|| {
+            (0..phases)
+                .map(|phase| {
+                    let frac = phase as f64 / phases as f64;
+                    (0..taps)
+                        .map(|k| kernel(k as f64 + 1.0 - half_width as f64 - frac))
+                        .collect()
+                })
+                .collect()
+        });
+
+        let output_len = ((samples.len() as u64 * phases as u64).div_ceil(step as u64)) as usize;
+        let mut output = Vec::with_capacity(output_len);
+
+        for i in 0..output_len {
+            let position = i as u64 * step as u64;
+            let base = (position / phases as u64) as isize;
+            let phase = (position % phases as u64) as usize;
+            let first = base + 1 - half_width as isize;
+
+            let mut acc = 0.0f32;
+            for k in 0..taps {
+                let index = first + k as isize;
+                if index < 0 || index as usize >= samples.len() {
+                    continue;
+                }
+                let weight = match &table {
+                    Some(table) => table[phase][k],
+                    None => kernel(k as f64 + 1.0 - half_width as f64 - phase as f64 / phases as f64),
+                };
+                acc += samples[index as usize] * weight;
+            }
+            output.push(acc);
+        }
+
+        output
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-12 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
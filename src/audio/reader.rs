@@ -1,68 +1,202 @@
-use anyhow::{Result, bail};
-use hound::WavReader;
+use crate::error::{RaagError, Result};
+use hound::{SampleFormat, WavReader};
+use std::ops::Range;
 use std::path::Path;
+use std::str::FromStr;
 use std::fs::File;
-use symphonia::core::audio::{AudioBufferRef, Signal};
-use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use std::io::{BufReader, ErrorKind, Read, Seek, SeekFrom};
+use symphonia::core::audio::{AudioBuffer, AudioBufferRef, Signal};
+use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
 use symphonia::core::errors::Error as SymphoniaError;
-use symphonia::core::formats::FormatOptions;
-use symphonia::core::io::MediaSourceStream;
+use symphonia::core::formats::{FormatOptions, FormatReader, Track};
+use symphonia::core::io::{MediaSource, MediaSourceStream};
 use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
+use symphonia::core::sample::Sample;
+
+/// Give up on a stream after this many malformed packets in a row
+const MAX_CONSECUTIVE_CORRUPT_PACKETS: usize = 100;
+
+/// Controls which part of a recording is decoded.
+///
+/// The default decodes the whole file from the beginning.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReadOptions {
+    /// Seconds to skip from the start of the recording
+    pub offset: f32,
+    /// Maximum number of seconds to decode after `offset` (`None` = until end of file)
+    pub max_duration: Option<f32>,
+}
+
+impl ReadOptions {
+    /// Frame range selected by these options at the given sample rate.
+    /// The end is `None` when decoding should run until the end of the file.
+    fn frame_range(&self, sample_rate: u32) -> (usize, Option<usize>) {
+        // f64, so that frames stay exact hours into a recording
+        let frames = |seconds: f32| (seconds.max(0.0) as f64 * sample_rate as f64).round() as usize;
+        let start = frames(self.offset);
+        let end = self.max_duration.map(|duration| start + frames(duration));
+        (start, end)
+    }
+}
+
+/// How multi-channel audio is reduced to the mono signal used for analysis
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DownmixStrategy {
+    /// Mean of all channels
+    #[default]
+    Average,
+    /// First channel only
+    Left,
+    /// Second channel only
+    Right,
+    /// The channel with the highest overall energy
+    MaxEnergy,
+}
+
+impl FromStr for DownmixStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "average" | "avg" | "mean" => Ok(Self::Average),
+            "left" | "l" => Ok(Self::Left),
+            "right" | "r" => Ok(Self::Right),
+            "max-energy" | "max_energy" | "loudest" => Ok(Self::MaxEnergy),
+            _ => Err(format!("Unknown downmix strategy: {} (expected average, left, right or max-energy)", s)),
+        }
+    }
+}
 
 pub struct AudioReader {
     pub samples: Vec<f32>,
     pub sample_rate: u32,
     pub channels: u16,
+    /// Position of the first decoded sample in the recording, in seconds
+    pub start_time: f64,
 }
 
 impl AudioReader {
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_file_with_options(path, &ReadOptions::default())
+    }
+
+    pub fn from_file_with_options<P: AsRef<Path>>(path: P, options: &ReadOptions) -> Result<Self> {
         let path = path.as_ref();
+        let file = File::open(path)?;
+
+        // The extension is only a hint for the prober; the content decides the format
+        let extension = path.extension().and_then(|ext| ext.to_str());
+
+        Self::decode(file, extension, options)
+    }
 
-        // Detect format by file extension
-        let extension = path.extension()
-            .and_then(|ext| ext.to_str())
-            .map(|ext| ext.to_lowercase());
+    /// Decode audio from any seekable byte stream, e.g. an in-memory `Cursor`.
+    /// The format is detected from the content alone.
+    pub fn from_reader<R>(reader: R) -> Result<Self>
+    where
+        R: Read + Seek + Send + Sync + 'static,
+    {
+        Self::from_reader_with_options(reader, &ReadOptions::default())
+    }
+
+    pub fn from_reader_with_options<R>(reader: R, options: &ReadOptions) -> Result<Self>
+    where
+        R: Read + Seek + Send + Sync + 'static,
+    {
+        Self::decode(reader, None, options)
+    }
+
+    fn decode<R>(mut source: R, extension: Option<&str>, options: &ReadOptions) -> Result<Self>
+    where
+        R: Read + Seek + Send + Sync + 'static,
+    {
+        // RIFF/WAVE goes through hound, which handles every PCM and float layout.
+        // Anything hound can't read (e.g. ADPCM) falls back to symphonia.
+        // The audio starts wherever the stream is, which need not be byte 0.
+        let start = source.stream_position()?;
+        if Self::is_riff_wave(&mut source, start)? {
+            match WavReader::new(BufReader::new(&mut source)) {
+                Ok(reader) => return Self::from_wav_reader(reader, options),
+                Err(hound::Error::Unsupported) | Err(hound::Error::FormatError(_)) => {
+                    source.seek(SeekFrom::Start(start))?;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
 
-        match extension.as_deref() {
-            Some("wav") => Self::from_wav_file(path),
-            Some("mp3") | Some("flac") | Some("ogg") => Self::from_symphonia_file(path),
-            Some(ext) => bail!("Unsupported audio format: .{}\nSupported formats: .wav, .mp3, .flac, .ogg", ext),
-            None => bail!("Could not determine audio format from file extension"),
+        let source = ReaderSource::new(source)?;
+        Self::from_symphonia_source(Box::new(source), extension, options)
+    }
+
+    /// Check the magic number of a RIFF/WAVE stream at `start` without
+    /// consuming it
+    fn is_riff_wave<R: Read + Seek>(source: &mut R, start: u64) -> Result<bool> {
+        let mut header = [0u8; 12];
+        let mut filled = 0;
+        while filled < header.len() {
+            match source.read(&mut header[filled..])? {
+                0 => break,
+                n => filled += n,
+            }
         }
+        source.seek(SeekFrom::Start(start))?;
+
+        Ok(filled == header.len() && &header[0..4] == b"RIFF" && &header[8..12] == b"WAVE")
     }
 
-    fn from_wav_file<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let mut reader = WavReader::open(path)?;
+    fn from_wav_reader<R: Read + Seek>(mut reader: WavReader<R>, options: &ReadOptions) -> Result<Self> {
         let spec = reader.spec();
 
-        let samples: Result<Vec<f32>, _> = reader
-            .samples::<i16>()
-            .map(|s| s.map(|sample| sample as f32 / 32768.0))
-            .collect();
-        let samples = samples?;
+        let (start, end) = options.frame_range(spec.sample_rate);
+        let total_frames = reader.duration() as usize;
+        let start = start.min(total_frames);
+        let frames = end.unwrap_or(total_frames).min(total_frames) - start;
+        reader.seek(start as u32)?;
+
+        let count = frames * spec.channels as usize;
+        let samples: Vec<f32> = match (spec.sample_format, spec.bits_per_sample) {
+            (SampleFormat::Float, 32) => reader
+                .samples::<f32>()
+                .take(count)
+                .collect::<std::result::Result<_, _>>()?,
+            (SampleFormat::Int, bits @ 1..=32) => {
+                // hound sign-extends every integer width (and re-centres unsigned
+                // 8-bit data), so one full-scale divisor per bit depth is enough
+                let full_scale = (1u64 << (bits - 1)) as f32;
+                reader
+                    .samples::<i32>()
+                    .take(count)
+                    .map(|s| s.map(|sample| sample as f32 / full_scale))
+                    .collect::<std::result::Result<_, _>>()?
+            }
+            (format, bits) => {
+                return Err(RaagError::UnsupportedSampleFormat {
+                    bits,
+                    format: format!("{:?}", format),
+                })
+            }
+        };
+
+        if samples.is_empty() {
+            return Err(RaagError::NoAudioData);
+        }
 
         Ok(AudioReader {
             samples,
             sample_rate: spec.sample_rate,
             channels: spec.channels,
+            start_time: start as f64 / spec.sample_rate as f64,
         })
     }
 
-    fn from_symphonia_file<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let path_ref = path.as_ref();
+    fn from_symphonia_source(source: Box<dyn MediaSource>, extension: Option<&str>, options: &ReadOptions) -> Result<Self> {
+        let mss = MediaSourceStream::new(source, Default::default());
 
-        // Open the media source
-        let file = File::open(path_ref)?;
-        let mss = MediaSourceStream::new(Box::new(file), Default::default());
-
-        // Create a probe hint using the file extension
+        // Use the file extension, if any, as a hint for the probe
         let mut hint = Hint::new();
-        if let Some(extension) = path_ref.extension() {
-            if let Some(extension_str) = extension.to_str() {
-                hint.with_extension(extension_str);
-            }
+        if let Some(extension) = extension {
+            hint.with_extension(extension);
         }
 
         // Use the default options for metadata and format readers
@@ -70,30 +204,28 @@ impl AudioReader {
         let fmt_opts: FormatOptions = Default::default();
 
         // Probe the media source
-        let probed = symphonia::default::get_probe().format(&hint, mss, &fmt_opts, &meta_opts)?;
-
-        // Get the instantiated format reader
-        let mut format = probed.format;
+        let probed = symphonia::default::get_probe()
+            .format(&hint, mss, &fmt_opts, &meta_opts)
+            .map_err(|err| match err {
+                SymphoniaError::Unsupported(_) => RaagError::UnrecognizedFormat,
+                err => RaagError::from(err),
+            })?;
+
+        Self::from_format_reader(probed.format, options)
+    }
 
+    /// Decode the first decodable track of an already opened symphonia
+    /// format reader, such as a custom demuxer. Resets are handled by picking
+    /// the track again and recreating its decoder.
+    pub fn from_format_reader(mut format: Box<dyn FormatReader>, options: &ReadOptions) -> Result<Self> {
         // Find the first audio track with a known (decodeable) codec
-        let track = format.tracks()
-            .iter()
-            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
-            .ok_or_else(|| anyhow::anyhow!("No supported audio tracks found"))?;
-
-        // Use the default options for the decoder
-        let dec_opts: DecoderOptions = Default::default();
-
-        // Create a decoder for the track
-        let mut decoder = symphonia::default::get_codecs().make(&track.codec_params, &dec_opts)?;
-
-        // Store the track identifier, we'll use it to filter packets
-        let track_id = track.id;
+        let (mut track_id, mut decoder) = Self::select_track(format.as_ref(), None)?;
 
         let mut samples = Vec::new();
         let mut sample_rate = 0u32;
         let mut channels = 0u16;
-        let max_samples = 10 * 44100; // Limit to first 10 seconds for testing
+        let mut window = FrameWindow::new(options);
+        let mut corrupt_packets = 0usize;
 
         // The decode loop
         loop {
@@ -101,11 +233,13 @@ impl AudioReader {
             let packet = match format.next_packet() {
                 Ok(packet) => packet,
                 Err(SymphoniaError::ResetRequired) => {
-                    // The track list has been changed. Re-examine it and create a new set of decoders,
-                    // then restart the decode loop. This is an advanced feature and we ignore it.
-                    unimplemented!();
+                    // The track list has changed (e.g. a new logical stream in a chained
+                    // OGG file). Re-select a track, preferring the one we were decoding,
+                    // and start a fresh decoder for it.
+                    (track_id, decoder) = Self::select_track(format.as_ref(), Some(track_id))?;
+                    continue;
                 }
-                Err(SymphoniaError::IoError(_)) => {
+                Err(SymphoniaError::IoError(err)) if err.kind() == ErrorKind::UnexpectedEof => {
                     // The packet reader has reached the end of the media, exit the loop
                     break;
                 }
@@ -128,154 +262,291 @@ impl AudioReader {
 
             // Decode the packet into an audio buffer
             let decoded_buffer = match decoder.decode(&packet) {
-                Ok(buf) => buf,
+                Ok(buf) => {
+                    corrupt_packets = 0;
+                    buf
+                }
                 Err(SymphoniaError::DecodeError(_)) => {
-                    // Skip malformed frames and continue
+                    // Skip malformed frames and continue, unless the stream is unreadable
+                    corrupt_packets += 1;
+                    if corrupt_packets >= MAX_CONSECUTIVE_CORRUPT_PACKETS {
+                        return Err(RaagError::CorruptStream(corrupt_packets));
+                    }
+                    continue;
+                }
+                Err(SymphoniaError::ResetRequired) => {
+                    // The stream parameters changed; the decoder must be recreated
+                    (track_id, decoder) = Self::select_track(format.as_ref(), Some(track_id))?;
                     continue;
                 }
                 Err(err) => return Err(err.into()),
             };
 
+            let spec = *decoded_buffer.spec();
+            let buffer_channels = spec.channels.count() as u16;
+            if sample_rate != 0 && (spec.rate != sample_rate || buffer_channels != channels) {
+                // Samples are accumulated into a single interleaved stream, so a
+                // chained stream with a different layout can't be appended
+                return Err(RaagError::StreamChanged {
+                    sample_rate,
+                    channels,
+                    new_sample_rate: spec.rate,
+                    new_channels: buffer_channels,
+                });
+            }
+            sample_rate = spec.rate;
+            channels = buffer_channels;
+            window.start(sample_rate);
+
             match decoded_buffer {
                 AudioBufferRef::F32(buf) => {
-                    sample_rate = buf.spec().rate;
-                    channels = buf.spec().channels.count() as u16;
-
-                    // Convert planar to interleaved
-                    for ch in 0..buf.spec().channels.count() {
-                        let channel = buf.chan(ch);
-                        for &sample in channel.iter() {
-                            samples.push(sample);
-                            if samples.len() >= max_samples {
-                                break;
-                            }
-                        }
-                        if samples.len() >= max_samples {
-                            break;
-                        }
-                    }
+                    Self::append_interleaved(&buf, |s| s, &mut window, &mut samples);
                 }
                 AudioBufferRef::U8(buf) => {
-                    sample_rate = buf.spec().rate;
-                    channels = buf.spec().channels.count() as u16;
-
-                    for ch in 0..buf.spec().channels.count() {
-                        let channel = buf.chan(ch);
-                        for &sample in channel.iter() {
-                            samples.push((sample as f32 - 128.0) / 128.0);
-                        }
-                    }
+                    Self::append_interleaved(&buf, |s| (s as f32 - 128.0) / 128.0, &mut window, &mut samples);
                 }
                 AudioBufferRef::U16(buf) => {
-                    sample_rate = buf.spec().rate;
-                    channels = buf.spec().channels.count() as u16;
-
-                    for ch in 0..buf.spec().channels.count() {
-                        let channel = buf.chan(ch);
-                        for &sample in channel.iter() {
-                            samples.push((sample as f32 - 32768.0) / 32768.0);
-                        }
-                    }
+                    Self::append_interleaved(&buf, |s| (s as f32 - 32768.0) / 32768.0, &mut window, &mut samples);
                 }
                 AudioBufferRef::U24(buf) => {
-                    sample_rate = buf.spec().rate;
-                    channels = buf.spec().channels.count() as u16;
-
-                    for ch in 0..buf.spec().channels.count() {
-                        let channel = buf.chan(ch);
-                        for &sample in channel.iter() {
-                            let sample_f32 = sample.inner() as f32;
-                            samples.push((sample_f32 - 8388608.0) / 8388608.0);
-                        }
-                    }
+                    Self::append_interleaved(&buf, |s| (s.inner() as f32 - 8388608.0) / 8388608.0, &mut window, &mut samples);
                 }
                 AudioBufferRef::U32(buf) => {
-                    sample_rate = buf.spec().rate;
-                    channels = buf.spec().channels.count() as u16;
-
-                    for ch in 0..buf.spec().channels.count() {
-                        let channel = buf.chan(ch);
-                        for &sample in channel.iter() {
-                            samples.push((sample as f32 - 2147483648.0) / 2147483648.0);
-                        }
-                    }
+                    Self::append_interleaved(&buf, |s| (s as f32 - 2147483648.0) / 2147483648.0, &mut window, &mut samples);
                 }
                 AudioBufferRef::S8(buf) => {
-                    sample_rate = buf.spec().rate;
-                    channels = buf.spec().channels.count() as u16;
-
-                    for ch in 0..buf.spec().channels.count() {
-                        let channel = buf.chan(ch);
-                        for &sample in channel.iter() {
-                            samples.push(sample as f32 / 128.0);
-                        }
-                    }
+                    Self::append_interleaved(&buf, |s| s as f32 / 128.0, &mut window, &mut samples);
                 }
                 AudioBufferRef::S16(buf) => {
-                    sample_rate = buf.spec().rate;
-                    channels = buf.spec().channels.count() as u16;
-
-                    for ch in 0..buf.spec().channels.count() {
-                        let channel = buf.chan(ch);
-                        for &sample in channel.iter() {
-                            samples.push(sample as f32 / 32768.0);
-                        }
-                    }
+                    Self::append_interleaved(&buf, |s| s as f32 / 32768.0, &mut window, &mut samples);
                 }
                 AudioBufferRef::S24(buf) => {
-                    sample_rate = buf.spec().rate;
-                    channels = buf.spec().channels.count() as u16;
-
-                    for ch in 0..buf.spec().channels.count() {
-                        let channel = buf.chan(ch);
-                        for &sample in channel.iter() {
-                            let sample_f32 = sample.inner() as f32;
-                            samples.push(sample_f32 / 8388608.0);
-                        }
-                    }
+                    Self::append_interleaved(&buf, |s| s.inner() as f32 / 8388608.0, &mut window, &mut samples);
                 }
                 AudioBufferRef::S32(buf) => {
-                    sample_rate = buf.spec().rate;
-                    channels = buf.spec().channels.count() as u16;
-
-                    for ch in 0..buf.spec().channels.count() {
-                        let channel = buf.chan(ch);
-                        for &sample in channel.iter() {
-                            samples.push(sample as f32 / 2147483648.0);
-                        }
-                    }
+                    Self::append_interleaved(&buf, |s| s as f32 / 2147483648.0, &mut window, &mut samples);
                 }
                 AudioBufferRef::F64(buf) => {
-                    sample_rate = buf.spec().rate;
-                    channels = buf.spec().channels.count() as u16;
-
-                    for ch in 0..buf.spec().channels.count() {
-                        let channel = buf.chan(ch);
-                        for &sample in channel.iter() {
-                            samples.push(sample as f32);
-                        }
-                    }
+                    Self::append_interleaved(&buf, |s| s as f32, &mut window, &mut samples);
                 }
             }
 
-            // Break if we've collected enough samples
-            if samples.len() >= max_samples {
+            // Stop once the requested duration has been collected
+            if window.is_complete() {
                 break;
             }
         }
 
         if samples.is_empty() {
-            bail!("No audio data found in file");
+            return Err(RaagError::NoAudioData);
         }
 
         Ok(AudioReader {
             samples,
             sample_rate,
             channels,
+            start_time: window.start as f64 / sample_rate as f64,
         })
     }
 
+    /// Pick the track to decode and create a decoder for it.
+    ///
+    /// `previous` is the track that was being decoded before a reset; it is
+    /// kept if it is still present, otherwise the first decodable track is used.
+    fn select_track(format: &dyn FormatReader, previous: Option<u32>) -> Result<(u32, Box<dyn Decoder>)> {
+        let decodable = |t: &&Track| t.codec_params.codec != CODEC_TYPE_NULL;
+
+        let track = previous
+            .and_then(|id| format.tracks().iter().filter(decodable).find(|t| t.id == id))
+            .or_else(|| format.tracks().iter().find(decodable))
+            .ok_or(RaagError::NoSupportedTrack)?;
+
+        // Use the default options for the decoder
+        let dec_opts: DecoderOptions = Default::default();
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &dec_opts)?;
+
+        Ok((track.id, decoder))
+    }
+
+    /// Append the part of a decoded buffer that falls inside the read window,
+    /// interleaving symphonia's planar channels frame by frame
+    fn append_interleaved<S: Sample>(
+        buf: &AudioBuffer<S>,
+        to_f32: impl Fn(S) -> f32,
+        window: &mut FrameWindow,
+        samples: &mut Vec<f32>,
+    ) {
+        let range = window.take(buf.frames());
+        if range.is_empty() {
+            return;
+        }
+
+        let planes: Vec<&[S]> = (0..buf.spec().channels.count())
+            .map(|ch| buf.chan(ch))
+            .collect();
+
+        samples.reserve(range.len() * planes.len());
+        for frame in range {
+            for plane in &planes {
+                samples.push(to_f32(plane[frame]));
+            }
+        }
+    }
+
+    /// Number of sample frames (one sample per channel)
+    pub fn frames(&self) -> usize {
+        if self.channels == 0 {
+            0
+        } else {
+            self.samples.len() / self.channels as usize
+        }
+    }
+
+    /// Samples of a single channel, or `None` if the channel does not exist
+    pub fn channel(&self, n: usize) -> Option<Vec<f32>> {
+        let channels = self.channels as usize;
+        if n >= channels {
+            return None;
+        }
+
+        Some(self.samples.iter().skip(n).step_by(channels).copied().collect())
+    }
+
+    /// Mix all channels down to a single channel using the given strategy
+    pub fn to_mono(&self, strategy: DownmixStrategy) -> Vec<f32> {
+        let channels = self.channels as usize;
+        if channels <= 1 {
+            return self.samples.clone();
+        }
+
+        match strategy {
+            DownmixStrategy::Average => self
+                .samples
+                .chunks_exact(channels)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+                .collect(),
+            DownmixStrategy::Left => self.channel(0).unwrap_or_default(),
+            DownmixStrategy::Right => self.channel(1).unwrap_or_default(),
+            DownmixStrategy::MaxEnergy => {
+                let mut energies = vec![0.0f64; channels];
+                for frame in self.samples.chunks_exact(channels) {
+                    for (energy, &sample) in energies.iter_mut().zip(frame) {
+                        *energy += (sample as f64) * (sample as f64);
+                    }
+                }
+
+                let loudest = energies
+                    .iter()
+                    .enumerate()
+                    .max_by(|a, b| a.1.total_cmp(b.1))
+                    .map(|(ch, _)| ch)
+                    .unwrap_or(0);
+
+                self.channel(loudest).unwrap_or_default()
+            }
+        }
+    }
+
     pub fn duration_seconds(&self) -> f32 {
-        self.samples.len() as f32 / (self.sample_rate as f32 * self.channels as f32)
+        self.frames() as f32 / self.sample_rate as f32
     }
-}
\ No newline at end of file
+}
+
+/// Tracks how many decoded frames are still to be skipped or kept while
+/// streaming packets, so every sample format honours the same `ReadOptions`.
+struct FrameWindow {
+    options: ReadOptions,
+    position: usize,
+    start: usize,
+    end: Option<usize>,
+}
+
+impl FrameWindow {
+    fn new(options: &ReadOptions) -> Self {
+        Self {
+            options: *options,
+            position: 0,
+            start: 0,
+            end: None,
+        }
+    }
+
+    /// Resolve the window in frames once the stream's sample rate is known
+    fn start(&mut self, sample_rate: u32) {
+        if self.position == 0 {
+            let (start, end) = self.options.frame_range(sample_rate);
+            self.start = start;
+            self.end = end;
+        }
+    }
+
+    /// Advance past a buffer of `frames` frames, returning the sub-range to keep
+    fn take(&mut self, frames: usize) -> Range<usize> {
+        let buffer_start = self.position;
+        let buffer_end = buffer_start + frames;
+        self.position = buffer_end;
+
+        let keep_start = self.start.clamp(buffer_start, buffer_end);
+        let keep_end = self.end.unwrap_or(buffer_end).clamp(keep_start, buffer_end);
+        (keep_start - buffer_start)..(keep_end - buffer_start)
+    }
+
+    fn is_complete(&self) -> bool {
+        self.end.is_some_and(|end| self.position >= end)
+    }
+}
+
+/// Adapts any seekable reader to symphonia's `MediaSource`
+struct ReaderSource<R> {
+    inner: R,
+    /// Position in `inner` of the stream's first byte, which positions seen
+    /// by symphonia count from
+    start: u64,
+    len: Option<u64>,
+}
+
+impl<R: Seek> ReaderSource<R> {
+    fn new(mut inner: R) -> Result<Self> {
+        let start = inner.stream_position()?;
+        let end = inner.seek(SeekFrom::End(0))?;
+        inner.seek(SeekFrom::Start(start))?;
+
+        Ok(Self {
+            inner,
+            start,
+            len: Some(end.saturating_sub(start)),
+        })
+    }
+}
+
+impl<R: Read> Read for ReaderSource<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl<R: Seek> Seek for ReaderSource<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(offset) => SeekFrom::Start(self.start + offset),
+            relative => relative,
+        };
+        let position = self.inner.seek(pos)?;
+        if position < self.start {
+            self.inner.seek(SeekFrom::Start(self.start))?;
+            return Err(std::io::Error::new(ErrorKind::InvalidInput, "seek before the start of the audio"));
+        }
+        Ok(position - self.start)
+    }
+}
+
+impl<R: Read + Seek + Send + Sync> MediaSource for ReaderSource<R> {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        self.len
+    }
+}
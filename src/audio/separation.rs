@@ -0,0 +1,242 @@
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+use std::f32::consts::PI;
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::features::FrameConfig;
+use crate::theory::{Swara, TuningSystem};
+
+/// Keeps masks and gains finite in silent bins
+const EPSILON: f32 = 1e-10;
+
+/// Settings for pulling the lead melody out of a mix (see
+/// `AudioPreprocessor::harmonic` and `AudioPreprocessor::lead`)
+#[derive(Debug, Clone, Copy)]
+pub struct SeparationConfig {
+    /// STFT window length in samples
+    pub fft_size: usize,
+    /// STFT hop in samples
+    pub hop_size: usize,
+    /// Length in frames of the median filter along time, which keeps
+    /// sustained (harmonic) sound
+    pub harmonic_frames: usize,
+    /// Length in bins of the median filter along frequency, which keeps
+    /// broadband (percussive) sound such as tabla strokes
+    pub percussive_bins: usize,
+    /// Exponent of the soft masks; higher separates more sharply
+    pub mask_power: f32,
+    /// Drone string tuned besides Sa: usually Pa, or Ma or Ni for raags that
+    /// omit Pa
+    pub drone_swara: Swara,
+    /// Half-width of each drone harmonic, in cents; never narrower than one bin
+    pub drone_width_cents: f32,
+    /// Multiple of the drone's sustained level subtracted from its harmonics
+    pub drone_strength: f32,
+    /// STFT frames held in memory at once; longer recordings are separated a
+    /// block at a time
+    pub block_frames: usize,
+}
+
+impl Default for SeparationConfig {
+    fn default() -> Self {
+        Self {
+            fft_size: 2048,
+            hop_size: 512,
+            harmonic_frames: 17,
+            percussive_bins: 17,
+            mask_power: 2.0,
+            drone_swara: Swara::Pa,
+            drone_width_cents: 40.0,
+            drone_strength: 1.5,
+            block_frames: 512,
+        }
+    }
+}
+
+/// Hann-windowed STFT of a signal, computed a block of frames at a time so
+/// that a long recording's spectrogram is never held whole
+pub(crate) struct Spectrogram<'a> {
+    samples: &'a [f32],
+    frame_config: FrameConfig,
+    window: Vec<f32>,
+    forward: Arc<dyn Fft<f32>>,
+    block_frames: usize,
+}
+
+impl<'a> Spectrogram<'a> {
+    pub(crate) fn new(samples: &'a [f32], config: &SeparationConfig) -> Self {
+        Self {
+            samples,
+            frame_config: FrameConfig::new(config.fft_size, config.hop_size),
+            window: hann(config.fft_size),
+            forward: FftPlanner::new().plan_fft_forward(config.fft_size),
+            block_frames: config.block_frames.max(1),
+        }
+    }
+
+    pub(crate) fn frame_count(&self) -> usize {
+        self.frame_config.frame_count(self.samples.len())
+    }
+
+    /// Consecutive ranges of at most `block_frames` frames covering the
+    /// spectrogram
+    pub(crate) fn blocks(&self) -> impl Iterator<Item = Range<usize>> {
+        let (count, size) = (self.frame_count(), self.block_frames);
+        (0..count).step_by(size).map(move |start| start..(start + size).min(count))
+    }
+
+    /// Spectra of the frames in `range`, keeping the bins up to Nyquist
+    pub(crate) fn spectra(&self, range: Range<usize>) -> Vec<Vec<Complex<f32>>> {
+        let bins = self.frame_config.window_size / 2 + 1;
+        self.frame_config
+            .frames(self.samples)
+            .skip(range.start)
+            .take(range.len())
+            .map(|frame| {
+                let mut buffer: Vec<Complex<f32>> = frame
+                    .iter()
+                    .zip(&self.window)
+                    .map(|(&sample, &weight)| Complex::new(sample * weight, 0.0))
+                    .collect();
+                self.forward.process(&mut buffer);
+                buffer.truncate(bins);
+                buffer
+            })
+            .collect()
+    }
+
+    /// Resynthesise by weighted overlap-add, one block at a time; `block`
+    /// gives the spectra of the frames in each range, scaled as wanted
+    pub(crate) fn resynthesize(&self, mut block: impl FnMut(Range<usize>) -> Vec<Vec<Complex<f32>>>) -> Vec<f32> {
+        let size = self.frame_config.window_size;
+        let len = self.samples.len();
+        let inverse = FftPlanner::new().plan_fft_inverse(size);
+        let mut output = vec![0.0f32; len];
+        let mut weights = vec![0.0f32; len];
+
+        for range in self.blocks() {
+            for (index, frame) in (range.start..).zip(block(range)) {
+                let mut buffer = vec![Complex::new(0.0, 0.0); size];
+                for (bin, &value) in frame.iter().enumerate() {
+                    buffer[bin] = value;
+                    if bin > 0 && bin < size - bin {
+                        buffer[size - bin] = value.conj();
+                    }
+                }
+                inverse.process(&mut buffer);
+
+                let start = self.frame_config.frame_start(index);
+                for (offset, (value, &weight)) in buffer.iter().zip(&self.window).enumerate() {
+                    let position = start + offset as isize;
+                    if position < 0 || position as usize >= len {
+                        continue;
+                    }
+                    output[position as usize] += value.re / size as f32 * weight;
+                    weights[position as usize] += weight * weight;
+                }
+            }
+        }
+
+        output
+            .iter()
+            .zip(&weights)
+            .map(|(&sample, &weight)| if weight > EPSILON { sample / weight } else { 0.0 })
+            .collect()
+    }
+}
+
+/// Harmonic part of the frames in `block`: their spectra under a soft mask
+/// built by median filtering across time (harmonic) and across frequency
+/// (percussive). The filter along time reads up to `harmonic_frames / 2`
+/// frames either side of the block.
+pub(crate) fn harmonic_block(
+    spectrogram: &Spectrogram,
+    block: Range<usize>,
+    config: &SeparationConfig,
+) -> Vec<Vec<Complex<f32>>> {
+    let reach = config.harmonic_frames / 2;
+    let context = block.start.saturating_sub(reach)..(block.end + reach).min(spectrogram.frame_count());
+    let spectra = spectrogram.spectra(context.clone());
+    let magnitudes: Vec<Vec<f32>> = spectra
+        .iter()
+        .map(|frame| frame.iter().map(|bin| bin.norm()).collect())
+        .collect();
+    let frames = magnitudes.len();
+    let bins = magnitudes.first().map_or(0, Vec::len);
+    let across_frequency = config.percussive_bins / 2;
+    let mut scratch: Vec<f32> = Vec::new();
+
+    (block.start - context.start..block.end - context.start)
+        .map(|frame| {
+            (0..bins)
+                .map(|bin| {
+                    scratch.clear();
+                    let times = frame.saturating_sub(reach)..(frame + reach + 1).min(frames);
+                    scratch.extend(times.map(|t| magnitudes[t][bin]));
+                    let harmonic = median(&mut scratch).powf(config.mask_power);
+
+                    scratch.clear();
+                    let range = bin.saturating_sub(across_frequency)..(bin + across_frequency + 1).min(bins);
+                    scratch.extend_from_slice(&magnitudes[frame][range]);
+                    let percussive = median(&mut scratch).powf(config.mask_power);
+
+                    spectra[frame][bin] * (harmonic / (harmonic + percussive + EPSILON))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Bins on the harmonics of Sa and of the drone swara, both counted from the
+/// lower octave a tanpura sounds in
+pub(crate) fn drone_bins(sample_rate: u32, sa: f32, config: &SeparationConfig) -> Vec<bool> {
+    let bins = config.fft_size / 2 + 1;
+    let bin_hz = sample_rate as f32 / config.fft_size as f32;
+    // Tanpura strings are tuned by ear to pure intervals
+    let fundamentals =
+        [Swara::Sa, config.drone_swara].map(|swara| sa * TuningSystem::JustIntonation.ratio(swara) / 2.0);
+    let width = 2f32.powf(config.drone_width_cents / 1200.0) - 1.0;
+
+    (0..bins)
+        .map(|bin| {
+            let hz = bin as f32 * bin_hz;
+            sa > 0.0
+                && fundamentals.iter().any(|&fundamental| {
+                    let harmonic = (hz / fundamental).round().max(1.0) * fundamental;
+                    (hz - harmonic).abs() <= (harmonic * width).max(bin_hz)
+                })
+        })
+        .collect()
+}
+
+/// Level each bin holds throughout: its geometric mean over time, so only
+/// sound present nearly throughout keeps its level, which is what the drone
+/// is and the melody is not
+pub(crate) fn sustained_levels(spectrogram: &Spectrogram, config: &SeparationConfig) -> Vec<f32> {
+    let mut log_sums = vec![0.0f64; config.fft_size / 2 + 1];
+    for block in spectrogram.blocks() {
+        for frame in spectrogram.spectra(block) {
+            for (sum, value) in log_sums.iter_mut().zip(&frame) {
+                *sum += value.norm().max(EPSILON).ln() as f64;
+            }
+        }
+    }
+    let count = spectrogram.frame_count().max(1) as f64;
+    log_sums.iter().map(|sum| (sum / count).exp() as f32).collect()
+}
+
+/// Gain that takes a drone's `sustained` level off a bin of `magnitude`
+pub(crate) fn drone_gain(magnitude: f32, sustained: f32, config: &SeparationConfig) -> f32 {
+    (1.0 - config.drone_strength * sustained / (magnitude + EPSILON)).max(0.0)
+}
+
+fn hann(size: usize) -> Vec<f32> {
+    (0..size)
+        .map(|i| 0.5 * (1.0 - (2.0 * PI * i as f32 / size as f32).cos()))
+        .collect()
+}
+
+fn median(values: &mut [f32]) -> f32 {
+    let middle = values.len() / 2;
+    *values.select_nth_unstable_by(middle, f32::total_cmp).1
+}
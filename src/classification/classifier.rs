@@ -1,67 +1,262 @@
-use super::{Raag, RaagDatabase};
-use anyhow::Result;
+use super::direction::{self, DirectionalProfile};
+use super::ornament::{self, Ornament, OrnamentDetector};
+use super::phrase::{self, PhraseSpotter};
+use super::transcription::{Transcriber, Transcription};
+use super::{
+    ClassificationResult, DistanceMetric, MatchEvidence, PitchClassDistribution, Raag, RaagCandidate, RaagDatabase,
+    TonicEstimate, TonicEstimator,
+};
+use crate::error::{RaagError, Result};
+use crate::features::{FeatureStream, LongTermSpectrum, PitchFrame};
+use crate::theory::TuningSystem;
 
+/// Default share of the aroha/avaroha match in a candidate's distance
+pub const DEFAULT_DIRECTION_WEIGHT: f32 = 0.5;
+
+/// Default weight of phrase evidence: hearing all of a raag's phrases rather
+/// than none multiplies the odds of the candidate by `e` to this power
+pub const DEFAULT_PHRASE_WEIGHT: f32 = 1.0;
+
+/// Default weight of ornament evidence, applied like the phrase weight
+pub const DEFAULT_ORNAMENT_WEIGHT: f32 = 0.5;
+
+/// Time-indexed features of one recording. Each stream carries its own
+/// framing, so streams with different hops can still be lined up by time.
 pub struct AudioFeatures {
-    pub pitch_contour: Vec<f32>,
-    pub chromagram: Vec<[f32; 12]>,
-    pub spectral_centroid: Vec<f32>,
+    pub pitch_contour: FeatureStream<PitchFrame>,
+    pub chromagram: FeatureStream<[f32; 12]>,
+    pub spectral_centroid: FeatureStream<f32>,
+    /// Long-term spectrum of the whole recording, used to find the drone
+    pub long_term_spectrum: Option<LongTermSpectrum>,
+}
+
+/// Values of every stream at one point in time
+#[derive(Debug, Clone, Copy)]
+pub struct AlignedFrame<'a> {
+    pub time: f64,
+    pub pitch: Option<&'a PitchFrame>,
+    pub chroma: Option<&'a [f32; 12]>,
+    pub spectral_centroid: Option<f32>,
+}
+
+impl AudioFeatures {
+    /// Look up all streams at `time` seconds into the recording
+    pub fn frame_at(&self, time: f64) -> AlignedFrame<'_> {
+        AlignedFrame {
+            time,
+            pitch: self.pitch_contour.at_time(time),
+            chroma: self.chromagram.at_time(time),
+            spectral_centroid: self.spectral_centroid.at_time(time).copied(),
+        }
+    }
+
+    /// All streams sampled at the pitch contour's frame times
+    pub fn aligned_to_pitch(&self) -> impl Iterator<Item = AlignedFrame<'_>> + '_ {
+        self.pitch_contour
+            .iter_timed()
+            .map(move |(time, pitch)| AlignedFrame {
+                pitch: Some(pitch),
+                ..self.frame_at(time)
+            })
+    }
 }
 
 pub struct RaagClassifier {
     database: RaagDatabase,
+    metric: DistanceMetric,
+    bins_per_octave: usize,
+    max_distance: Option<f32>,
+    tonic_estimator: TonicEstimator,
+    tonic: Option<f32>,
+    tuning: TuningSystem,
+    direction_weight: f32,
+    phrase_spotter: PhraseSpotter,
+    phrase_weight: f32,
+    transcriber: Transcriber,
+    ornament_detector: OrnamentDetector,
+    ornament_weight: f32,
 }
 
 impl RaagClassifier {
     pub fn new() -> Self {
         Self {
             database: RaagDatabase::new(),
+            metric: DistanceMetric::default(),
+            bins_per_octave: 12,
+            max_distance: None,
+            tonic_estimator: TonicEstimator::default(),
+            tonic: None,
+            tuning: TuningSystem::default(),
+            direction_weight: DEFAULT_DIRECTION_WEIGHT,
+            phrase_spotter: PhraseSpotter::default(),
+            phrase_weight: DEFAULT_PHRASE_WEIGHT,
+            transcriber: Transcriber::default(),
+            ornament_detector: OrnamentDetector::default(),
+            ornament_weight: DEFAULT_ORNAMENT_WEIGHT,
         }
     }
 
-    pub fn classify(&self, features: &AudioFeatures) -> Result<Option<String>> {
-        // Find the tonic (Sa) note first
-        let tonic_freq = self.estimate_tonic(&features.pitch_contour)?;
+    /// Raags to match against, instead of the bundled catalogue
+    pub fn with_database(mut self, database: RaagDatabase) -> Self {
+        self.database = database;
+        self
+    }
 
-        // Analyze the scale degrees relative to the tonic
-        let scale_analysis = self.analyze_scale_degrees(&features.pitch_contour, tonic_freq);
+    pub fn database(&self) -> &RaagDatabase {
+        &self.database
+    }
 
-        // Compare with known raag patterns
-        let best_match = self.find_best_raag_match(&scale_analysis);
+    /// Distance used to compare the recording's distribution with raag templates
+    pub fn with_metric(mut self, metric: DistanceMetric) -> Self {
+        self.metric = metric;
+        self
+    }
 
-        Ok(best_match)
+    /// Resolution of the pitch-class histogram: 12 for semitones, more for
+    /// finer cent resolution (e.g. 120 for 10-cent bins)
+    pub fn with_resolution(mut self, bins_per_octave: usize) -> Self {
+        self.bins_per_octave = bins_per_octave.max(1);
+        self
     }
 
-    fn estimate_tonic(&self, pitch_contour: &[f32]) -> Result<f32> {
-        // Simple histogram-based tonic estimation
-        // In a real implementation, this would be more sophisticated
-        let mut histogram = std::collections::HashMap::new();
+    /// Largest distance the best candidate may have; beyond it the result
+    /// abstains. `None` uses the metric's `default_max_distance`.
+    pub fn with_max_distance(mut self, max_distance: Option<f32>) -> Self {
+        self.max_distance = max_distance;
+        self
+    }
 
-        for &freq in pitch_contour {
-            if freq > 80.0 && freq < 2000.0 {
-                let semitone = (freq.log2() * 12.0).round() as i32;
-                *histogram.entry(semitone).or_insert(0) += 1;
-            }
+    pub fn with_tonic_estimator(mut self, estimator: TonicEstimator) -> Self {
+        self.tonic_estimator = estimator;
+        self
+    }
+
+    /// Use this Sa (in Hz) instead of estimating it, or `None` to estimate
+    pub fn with_tonic(mut self, tonic: Option<f32>) -> Self {
+        self.tonic = tonic;
+        self
+    }
+
+    /// Tuning that gives raag notes their pitches and maps sung pitches to swaras
+    pub fn with_tuning(mut self, tuning: TuningSystem) -> Self {
+        self.tuning = tuning;
+        self
+    }
+
+    /// How much the ascending and descending movements, compared with the
+    /// aroha and avaroha, count against the overall note distribution (0 to 1)
+    pub fn with_direction_weight(mut self, weight: f32) -> Self {
+        self.direction_weight = weight.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_phrase_spotter(mut self, spotter: PhraseSpotter) -> Self {
+        self.phrase_spotter = spotter;
+        self
+    }
+
+    /// How strongly hearing a raag's characteristic phrases raises its share;
+    /// 0 ignores phrases
+    pub fn with_phrase_weight(mut self, weight: f32) -> Self {
+        self.phrase_weight = weight.max(0.0);
+        self
+    }
+
+    /// Settings for the note transcription behind phrase and direction matching
+    pub fn with_transcriber(mut self, transcriber: Transcriber) -> Self {
+        self.transcriber = transcriber;
+        self
+    }
+
+    pub fn with_ornament_detector(mut self, detector: OrnamentDetector) -> Self {
+        self.ornament_detector = detector;
+        self
+    }
+
+    /// How strongly hearing a raag's characteristic ornaments raises its
+    /// score; 0 ignores ornaments
+    pub fn with_ornament_weight(mut self, weight: f32) -> Self {
+        self.ornament_weight = weight.max(0.0);
+        self
+    }
+
+    pub fn classify(&self, features: &AudioFeatures) -> Result<ClassificationResult> {
+        self.check(features)?;
+
+        // Find the tonic (Sa) note first
+        let tonic = self.estimate_tonic(&features.pitch_contour.frames, features.long_term_spectrum.as_ref())?;
+        self.classify_with_tonic(features, tonic)
+    }
+
+    /// Like `classify`, with a tonic already found, e.g. by `estimate_tonic`
+    /// on another contour of the same recording
+    pub fn classify_with_tonic(&self, features: &AudioFeatures, tonic: TonicEstimate) -> Result<ClassificationResult> {
+        self.check(features)?;
+
+        // Analyze the scale degrees relative to the tonic
+        let scale_analysis = self.analyze_scale_degrees(&features.pitch_contour.frames, tonic.frequency);
+
+        // Transcribe the steady notes for phrase and direction matching, and
+        // the ornaments between and around them
+        let transcription = self
+            .transcriber
+            .transcribe(&features.pitch_contour, tonic.frequency, &self.tuning);
+        let ornaments =
+            self.ornament_detector
+                .detect(&features.pitch_contour, tonic.frequency, &transcription, &self.tuning);
+
+        // Compare with known raag patterns
+        let mut candidates = self.rank_raags(&scale_analysis, &transcription, &ornaments);
+        if candidates.is_empty() {
+            return Err(RaagError::NoVoicedFrames);
         }
 
-        let most_frequent_semitone = histogram
-            .iter()
-            .max_by_key(|(_, &count)| count)
-            .map(|(&semitone, _)| semitone)
-            .unwrap_or(0);
+        // Shares only compare raags with each other, so whether any of them
+        // fits at all is judged on the distance
+        let max_distance = self.max_distance.unwrap_or_else(|| self.metric.default_max_distance());
+        for candidate in &mut candidates {
+            candidate.score = (1.0 - candidate.distance / max_distance).clamp(0.0, 1.0);
+        }
+        let abstained = candidates[0].distance > max_distance;
+        Ok(ClassificationResult {
+            candidates,
+            tonic,
+            transcription,
+            ornaments,
+            abstained,
+        })
+    }
 
-        // Convert back to frequency (assuming A4 = 440 Hz)
-        let tonic_freq = 440.0 * 2.0_f32.powf((most_frequent_semitone - 69) as f32 / 12.0);
+    fn check(&self, features: &AudioFeatures) -> Result<()> {
+        if self.database.get_raags().is_empty() {
+            return Err(RaagError::EmptyDatabase);
+        }
+        if features.pitch_contour.is_empty() {
+            return Err(RaagError::AudioTooShort);
+        }
+        Ok(())
+    }
 
-        Ok(tonic_freq)
+    /// The configured tonic, or one estimated from a pitch contour and the
+    /// long-term spectrum of the recording
+    pub fn estimate_tonic(
+        &self,
+        pitch_contour: &[PitchFrame],
+        spectrum: Option<&LongTermSpectrum>,
+    ) -> Result<TonicEstimate> {
+        match self.tonic {
+            Some(frequency) => Ok(TonicEstimate::user_supplied(frequency)),
+            None => self.tonic_estimator.estimate(pitch_contour, spectrum),
+        }
     }
 
-    fn analyze_scale_degrees(&self, pitch_contour: &[f32], tonic: f32) -> Vec<f32> {
+    fn analyze_scale_degrees(&self, pitch_contour: &[PitchFrame], tonic: f32) -> Vec<f32> {
         // Convert pitches to scale degrees (ratios to tonic)
         pitch_contour
             .iter()
-            .map(|&freq| {
-                if freq > 0.0 {
-                    freq / tonic
+            .map(|frame| {
+                if frame.is_voiced() {
+                    frame.frequency / tonic
                 } else {
                     0.0
                 }
@@ -69,15 +264,103 @@ impl RaagClassifier {
             .collect()
     }
 
-    fn find_best_raag_match(&self, _scale_degrees: &[f32]) -> Option<String> {
-        // Simplified raag matching based on scale degree analysis
-        // This is a placeholder - real implementation would be much more sophisticated
+    /// Score every raag against the recording, best first
+    fn rank_raags(
+        &self,
+        scale_degrees: &[f32],
+        transcription: &Transcription,
+        ornaments: &[Ornament],
+    ) -> Vec<RaagCandidate> {
+        let observed = PitchClassDistribution::from_scale_degrees(scale_degrees, self.bins_per_octave);
+        if observed.is_empty() {
+            return Vec::new();
+        }
+
+        let directional =
+            DirectionalProfile::from_movements(&direction::movements(&transcription.notes), self.bins_per_octave);
 
+        let mut candidates: Vec<RaagCandidate> = self
+            .database
+            .get_raags()
+            .iter()
+            .map(|raag| {
+                let template = PitchClassDistribution::from_raag(raag, self.bins_per_octave, &self.tuning);
+                let tuning = raag.tuning(&self.tuning);
+                let (aroha_distance, avaroha_distance) = directional.distances(
+                    &DirectionalProfile::from_raag(raag, self.bins_per_octave, &self.tuning),
+                    self.metric,
+                );
+                let overall = observed.distance(&template, self.metric);
+                let phrase_matches = self.phrase_spotter.spot(raag, &self.tuning, &transcription.notes);
+                let ornament_matches = ornament::heard_ornaments(raag, ornaments);
+                RaagCandidate {
+                    name: raag.name.clone(),
+                    score: 0.0,
+                    share: 0.0,
+                    distance: self.combined_distance(overall, aroha_distance, avaroha_distance),
+                    evidence: MatchEvidence {
+                        vadi_strength: observed.mass_at(raag.vadi.ratio(&tuning)),
+                        samvadi_strength: observed.mass_at(raag.samvadi.ratio(&tuning)),
+                        phrase_score: phrase::phrase_score(raag, &phrase_matches),
+                        phrase_matches,
+                        ornament_score: ornament::ornament_score(raag, &ornament_matches),
+                        ornament_matches,
+                        aroha_distance,
+                        avaroha_distance,
+                    },
+                }
+            })
+            .collect();
+
+        // Softmax over negative distances, moved up or down by the phrases
+        // and ornaments heard, if any notes were transcribed to search
+        let temperature = self.metric.temperature();
+        let logit = |(raag, c): (&Raag, &RaagCandidate)| {
+            let mut logit = -c.distance / temperature;
+            if !transcription.is_empty() {
+                let (phrases, ornaments) = (!raag.characteristic_phrases.is_empty(), !raag.ornaments.is_empty());
+                logit += evidence_term(self.phrase_weight, c.evidence.phrase_score, phrases);
+                logit += evidence_term(self.ornament_weight, c.evidence.ornament_score, ornaments);
+            }
+            logit
+        };
         let raags = self.database.get_raags();
-        if !raags.is_empty() {
-            Some(raags[0].name.clone())
-        } else {
-            None
+        let logits: Vec<f32> = raags.iter().zip(&candidates).map(logit).collect();
+        let highest = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let weights: Vec<f32> = logits.iter().map(|logit| (logit - highest).exp()).collect();
+        let total: f32 = weights.iter().sum();
+        for (candidate, weight) in candidates.iter_mut().zip(weights) {
+            candidate.share = weight / total;
+        }
+
+        candidates.sort_by(|a, b| b.share.total_cmp(&a.share));
+        candidates
+    }
+
+    /// Blend the overall distance with the mean of the directional ones
+    fn combined_distance(&self, overall: f32, aroha: Option<f32>, avaroha: Option<f32>) -> f32 {
+        let directional: Vec<f32> = aroha.into_iter().chain(avaroha).collect();
+        if directional.is_empty() {
+            return overall;
         }
+        let mean = directional.iter().sum::<f32>() / directional.len() as f32;
+        (1.0 - self.direction_weight) * overall + self.direction_weight * mean
+    }
+}
+
+/// Logit term for one kind of evidence, centred so that hearing none of what a
+/// raag lists counts against it as much as hearing all of it counts for it.
+/// Raags that list none of that kind are left where their distance puts them.
+fn evidence_term(weight: f32, score: f32, listed: bool) -> f32 {
+    if listed {
+        weight * (score - 0.5)
+    } else {
+        0.0
     }
-}
\ No newline at end of file
+}
+
+impl Default for RaagClassifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
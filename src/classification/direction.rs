@@ -0,0 +1,108 @@
+use std::cmp::Ordering;
+
+use serde::Serialize;
+
+use super::{DistanceMetric, Note, PitchClassDistribution, Raag, TranscribedNote};
+use crate::theory::TuningSystem;
+
+/// Which way the melody is moving
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Direction {
+    Ascending,
+    Descending,
+}
+
+/// A run of transcribed notes that keeps moving in one direction
+#[derive(Debug, Clone, PartialEq)]
+pub struct Movement {
+    pub direction: Direction,
+    /// Notes from the start to the turning point, inclusive of both
+    pub notes: Vec<TranscribedNote>,
+}
+
+/// Split a note sequence into ascending and descending runs. The note where
+/// the melody turns ends one run and starts the next; repeated notes don't
+/// change direction.
+pub fn movements(notes: &[TranscribedNote]) -> Vec<Movement> {
+    let mut movements: Vec<Movement> = Vec::new();
+    for pair in notes.windows(2) {
+        let (from, to) = (pair[0].note.semitones(), pair[1].note.semitones());
+        let direction = match to.cmp(&from) {
+            Ordering::Greater => Direction::Ascending,
+            Ordering::Less => Direction::Descending,
+            Ordering::Equal => {
+                if let Some(movement) = movements.last_mut() {
+                    movement.notes.push(pair[1]);
+                }
+                continue;
+            }
+        };
+        match movements.last_mut() {
+            Some(movement) if movement.direction == direction => movement.notes.push(pair[1]),
+            _ => movements.push(Movement {
+                direction,
+                notes: pair.to_vec(),
+            }),
+        }
+    }
+    movements
+}
+
+/// Separate pitch-class distributions of a melody's ascending and descending
+/// movements, or of a raag's aroha and avaroha
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectionalProfile {
+    pub ascending: PitchClassDistribution,
+    pub descending: PitchClassDistribution,
+}
+
+impl DirectionalProfile {
+    /// Profile of a performance, each note placed at the pitch it was sung at
+    /// and weighted by how long it was held. Sung pitches, like a raag's
+    /// template, keep intonation finer than the swara, such as Darbari's
+    /// ati-komal Ga.
+    pub fn from_movements(movements: &[Movement], bins_per_octave: usize) -> Self {
+        let distribution = |direction: Direction| {
+            let notes = movements
+                .iter()
+                .filter(|movement| movement.direction == direction)
+                .flat_map(|movement| &movement.notes)
+                .map(|transcribed| (transcribed.cents, transcribed.duration() as f32));
+            PitchClassDistribution::from_weighted_cents(notes, bins_per_octave)
+        };
+
+        Self {
+            ascending: distribution(Direction::Ascending),
+            descending: distribution(Direction::Descending),
+        }
+    }
+
+    /// Expected profile of a raag: the notes of its aroha going up and of its
+    /// avaroha coming down
+    pub fn from_raag(raag: &Raag, bins_per_octave: usize, tuning: &TuningSystem) -> Self {
+        let tuning = raag.tuning(tuning);
+        let distribution = |notes: &[Note]| {
+            let mut cents: Vec<f32> = notes.iter().map(|note| note.cents(&tuning).rem_euclid(1200.0)).collect();
+            cents.sort_by(f32::total_cmp);
+            cents.dedup();
+            PitchClassDistribution::from_weighted_cents(cents.into_iter().map(|c| (c, 1.0)), bins_per_octave)
+        };
+
+        Self {
+            ascending: distribution(&raag.aroha),
+            descending: distribution(&raag.avaroha),
+        }
+    }
+
+    /// Distances of the ascending and descending distributions from a
+    /// template; `None` for a direction the performance never moved in
+    pub fn distances(&self, template: &DirectionalProfile, metric: DistanceMetric) -> (Option<f32>, Option<f32>) {
+        let distance = |observed: &PitchClassDistribution, expected: &PitchClassDistribution| {
+            (!observed.is_empty()).then(|| observed.distance(expected, metric))
+        };
+        (
+            distance(&self.ascending, &template.ascending),
+            distance(&self.descending, &template.descending),
+        )
+    }
+}
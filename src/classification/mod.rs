@@ -1,5 +1,24 @@
 pub mod raag_db;
 pub mod classifier;
+pub mod direction;
+pub mod phrase;
+pub mod ornament;
+pub mod transcription;
+pub mod pcd;
+pub mod result;
+pub mod tonic;
+pub mod validation;
 
-pub use raag_db::{Raag, RaagDatabase};
-pub use classifier::{RaagClassifier, AudioFeatures};
\ No newline at end of file
+pub use raag_db::{Note, PhrasePattern, Raag, RaagDatabase};
+pub use pcd::{DistanceMetric, PitchClassDistribution};
+pub use result::{ClassificationResult, MatchEvidence, RaagCandidate};
+pub use tonic::{parse_tonic, TonicEstimate, TonicEstimator, TonicSource};
+pub use validation::{Diagnostic, DiagnosticKind, NoteRole, Severity, THAATS};
+pub use direction::{Direction, DirectionalProfile, Movement};
+pub use phrase::{PhraseMatch, PhraseSpotter};
+pub use ornament::{Ornament, OrnamentDetector, OrnamentKind, OrnamentPattern};
+pub use transcription::{TranscribedNote, Transcriber, Transcription};
+pub use classifier::{
+    RaagClassifier, AudioFeatures, AlignedFrame, DEFAULT_DIRECTION_WEIGHT, DEFAULT_ORNAMENT_WEIGHT,
+    DEFAULT_PHRASE_WEIGHT,
+};
\ No newline at end of file
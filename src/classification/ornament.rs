@@ -0,0 +1,391 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::transcription::{nearest_note, TranscribedNote, Transcription};
+use super::{Note, Raag};
+use crate::features::{FeatureStream, PitchFrame};
+use crate::theory::{Swara, TuningSystem};
+
+/// What an ornament did, with the notes involved
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OrnamentKind {
+    /// Meend: a continuous glide from one steady note into the next
+    Meend { from: Note, to: Note },
+    /// Andolan or gamak: the pitch swinging to and fro around a note
+    Oscillation {
+        note: Note,
+        /// Full cycles per second
+        rate_hz: f32,
+        /// Mean distance between peak and trough
+        depth_cents: f32,
+    },
+    /// Kan: a brief touch of the `grace` note on the way into `note`
+    Kan { grace: Note, note: Note },
+}
+
+/// One ornament heard in the recording
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Ornament {
+    #[serde(flatten)]
+    pub kind: OrnamentKind,
+    /// Start of the ornament, in seconds
+    pub onset: f64,
+    /// End of the ornament, in seconds
+    pub offset: f64,
+}
+
+impl Ornament {
+    pub fn duration(&self) -> f64 {
+        self.offset - self.onset
+    }
+}
+
+impl fmt::Display for Ornament {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            OrnamentKind::Meend { from, to } => write!(f, "meend {} -> {}", from, to),
+            OrnamentKind::Oscillation {
+                note,
+                rate_hz,
+                depth_cents,
+            } => write!(f, "oscillation on {} ({:.1} Hz, {:.0} cents)", note, rate_hz, depth_cents),
+            OrnamentKind::Kan { grace, note } => write!(f, "kan {} into {}", grace, note),
+        }
+    }
+}
+
+/// An ornament a raag is known for, on swaras in any octave
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OrnamentPattern {
+    /// Oscillation around `swara`, e.g. Bhairav's andolan on komal Re. A
+    /// `max_rate_hz` tells a slow andolan apart from vibrato.
+    Oscillation {
+        swara: Swara,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_rate_hz: Option<f32>,
+    },
+    /// Glide from one swara to another
+    Meend { from: Swara, to: Swara },
+    /// `grace` touched on the way into `swara`
+    Kan { grace: Swara, swara: Swara },
+}
+
+impl OrnamentPattern {
+    /// True if `ornament` is an instance of this pattern
+    pub fn matches(&self, ornament: &Ornament) -> bool {
+        match (*self, ornament.kind) {
+            (
+                OrnamentPattern::Oscillation { swara, max_rate_hz },
+                OrnamentKind::Oscillation { note, rate_hz, .. },
+            ) => note.swara == swara && max_rate_hz.is_none_or(|max| rate_hz <= max),
+            (OrnamentPattern::Meend { from, to }, OrnamentKind::Meend { from: start, to: end }) => {
+                start.swara == from && end.swara == to
+            }
+            (OrnamentPattern::Kan { grace, swara }, OrnamentKind::Kan { grace: touched, note }) => {
+                touched.swara == grace && note.swara == swara
+            }
+            _ => false,
+        }
+    }
+
+    /// Every swara the pattern names
+    pub fn swaras(&self) -> Vec<Swara> {
+        match *self {
+            OrnamentPattern::Oscillation { swara, .. } => vec![swara],
+            OrnamentPattern::Meend { from, to } => vec![from, to],
+            OrnamentPattern::Kan { grace, swara } => vec![grace, swara],
+        }
+    }
+}
+
+/// Finds ornaments in a pitch contour: meends and kans in the frames leading
+/// into each transcribed note, and oscillations anywhere the pitch swings
+/// back and forth at a steady rate
+#[derive(Debug, Clone, Copy)]
+pub struct OrnamentDetector {
+    /// Shortest glide between two notes reported as a meend, in seconds
+    pub min_glide: f64,
+    /// Longest touch of another note reported as a kan, in seconds
+    pub max_kan: f64,
+    /// Shortest transcribed note that meends and kans lead into, in seconds;
+    /// shorter notes pass between them and may be the kan itself
+    pub min_note: f64,
+    /// How far, in cents, the pitch must go beyond the notes either side of
+    /// it to count as touching a kan
+    pub kan_reach_cents: f32,
+    /// Smallest swing from peak to trough counted towards an oscillation
+    pub min_depth_cents: f32,
+    /// Largest swing counted towards an oscillation; wider is a leap
+    pub max_depth_cents: f32,
+    /// Slowest oscillation reported, in full cycles per second
+    pub min_rate_hz: f32,
+    /// Fastest oscillation reported, in full cycles per second
+    pub max_rate_hz: f32,
+    /// Fewest full cycles reported as an oscillation
+    pub min_cycles: f32,
+    /// Largest pitch change between neighbouring frames within an
+    /// oscillation; a bigger jump is a change of note (or of the drone
+    /// showing through a pause), not a swing
+    pub max_jump_cents: f32,
+}
+
+impl Default for OrnamentDetector {
+    fn default() -> Self {
+        Self {
+            min_glide: 0.08,
+            max_kan: 0.08,
+            min_note: 0.12,
+            kan_reach_cents: 50.0,
+            min_depth_cents: 20.0,
+            max_depth_cents: 400.0,
+            min_rate_hz: 0.5,
+            max_rate_hz: 10.0,
+            min_cycles: 1.5,
+            max_jump_cents: 100.0,
+        }
+    }
+}
+
+impl OrnamentDetector {
+    /// Ornaments of `contour` against the tonic `sa` (in Hz), in order of
+    /// onset. `transcription` gives the steady notes glides start and end on;
+    /// notes shorter than `min_note` are passed over.
+    pub fn detect(
+        &self,
+        contour: &FeatureStream<PitchFrame>,
+        sa: f32,
+        transcription: &Transcription,
+        tuning: &TuningSystem,
+    ) -> Vec<Ornament> {
+        let cents: Vec<Option<f32>> = contour
+            .frames
+            .iter()
+            .map(|frame| (frame.is_voiced() && sa > 0.0).then(|| 1200.0 * (frame.frequency / sa).log2()))
+            .collect();
+
+        // Half a hop of slack so a note of exactly `min_note` counts
+        let min_note = self.min_note - contour.info.hop_duration() / 2.0;
+        let held: Vec<TranscribedNote> =
+            transcription.notes.iter().filter(|note| note.duration() >= min_note).copied().collect();
+        let mut ornaments = self.approaches(contour, &cents, &held, tuning);
+        let mut start = 0;
+        let continuous = |a: &Option<f32>, b: &Option<f32>| match (a, b) {
+            (Some(a), Some(b)) => (b - a).abs() <= self.max_jump_cents,
+            (a, b) => a.is_none() && b.is_none(),
+        };
+        for run in cents.chunk_by(continuous) {
+            if run[0].is_some() {
+                let values: Vec<f32> = run.iter().flatten().copied().collect();
+                ornaments.extend(self.oscillations(contour, start, &values, tuning));
+            }
+            start += run.len();
+        }
+        ornaments.sort_by(|a, b| a.onset.total_cmp(&b.onset));
+        ornaments
+    }
+
+    /// Meends and kans in the voiced frames just before each note
+    fn approaches(
+        &self,
+        contour: &FeatureStream<PitchFrame>,
+        cents: &[Option<f32>],
+        notes: &[TranscribedNote],
+        tuning: &TuningSystem,
+    ) -> Vec<Ornament> {
+        let hop = contour.info.hop_duration();
+        let mut ornaments = Vec::new();
+
+        for (index, note) in notes.iter().enumerate() {
+            let previous = index.checked_sub(1).map(|i| notes[i]);
+            let mut first = note.start;
+            while first > previous.map_or(0, |p| p.end) && cents[first - 1].is_some() {
+                first -= 1;
+            }
+            let lead: Vec<f32> = cents[first..note.start].iter().flatten().copied().collect();
+            if lead.is_empty() {
+                continue;
+            }
+            // The previous note, if the pitch never broke off after it
+            let from = previous.filter(|p| p.end == first);
+            let (low, high) = from.map_or((note.cents, note.cents), |p| {
+                (p.cents.min(note.cents), p.cents.max(note.cents))
+            });
+
+            let outside: Vec<usize> = (0..lead.len())
+                .filter(|&i| lead[i] > high + self.kan_reach_cents || lead[i] < low - self.kan_reach_cents)
+                .collect();
+            if let (Some(&a), Some(&b)) = (outside.first(), outside.last()) {
+                if outside.len() < 2 || (b - a + 1) as f64 * hop > self.max_kan {
+                    continue;
+                }
+                let extreme = outside
+                    .iter()
+                    .map(|&i| lead[i])
+                    .max_by(|x, y| (x - note.cents).abs().total_cmp(&(y - note.cents).abs()))
+                    .expect("at least two frames");
+                if let Some(grace) = nearest_note(extreme, tuning).filter(|&grace| grace != note.note) {
+                    ornaments.push(Ornament {
+                        kind: OrnamentKind::Kan { grace, note: note.note },
+                        onset: contour.info.time_of(first + a),
+                        offset: note.onset,
+                    });
+                }
+                continue;
+            }
+
+            let Some(from) = from else {
+                continue;
+            };
+            if from.note == note.note || (lead.len() as f64 * hop) < self.min_glide {
+                continue;
+            }
+            let rising = note.cents > from.cents;
+            let (mut toward, mut against) = (0, 0);
+            for step in lead.windows(2).map(|pair| pair[1] - pair[0]).filter(|step| step.abs() >= 1.0) {
+                if (step > 0.0) == rising {
+                    toward += 1;
+                } else {
+                    against += 1;
+                }
+            }
+            if toward >= 2 && toward as f32 >= 0.7 * (toward + against) as f32 {
+                ornaments.push(Ornament {
+                    kind: OrnamentKind::Meend {
+                        from: from.note,
+                        to: note.note,
+                    },
+                    onset: from.offset,
+                    offset: note.onset,
+                });
+            }
+        }
+        ornaments
+    }
+
+    /// Oscillations in one continuous run of `values` starting at frame `start`:
+    /// sequences of turning points evenly spaced in time and similar in depth
+    fn oscillations(
+        &self,
+        contour: &FeatureStream<PitchFrame>,
+        start: usize,
+        values: &[f32],
+        tuning: &TuningSystem,
+    ) -> Vec<Ornament> {
+        // Shortest and longest half cycle, in frames
+        let frame_rate = contour.info.frame_rate();
+        let half_cycles = frame_rate / (2.0 * self.max_rate_hz as f64)..=frame_rate / (2.0 * self.min_rate_hz as f64);
+
+        let mut ornaments = Vec::new();
+        let mut group: Vec<(usize, f32)> = Vec::new();
+        for extremum in self.extrema(values) {
+            if let Some(&(index, value)) = group.last() {
+                let fits = half_cycles.contains(&((extremum.0 - index) as f64))
+                    && (extremum.1 - value).abs() <= self.max_depth_cents;
+                if !fits {
+                    ornaments.extend(self.oscillation(contour, start, &group, tuning));
+                    group.clear();
+                }
+            }
+            group.push(extremum);
+        }
+        ornaments.extend(self.oscillation(contour, start, &group, tuning));
+        ornaments
+    }
+
+    /// An oscillation through these turning points, if there are enough of them
+    fn oscillation(
+        &self,
+        contour: &FeatureStream<PitchFrame>,
+        start: usize,
+        extrema: &[(usize, f32)],
+        tuning: &TuningSystem,
+    ) -> Option<Ornament> {
+        let swings = extrema.len().checked_sub(1)?;
+        if (swings as f32) < 2.0 * self.min_cycles {
+            return None;
+        }
+        let onset = contour.info.time_of(start + extrema[0].0);
+        let offset = contour.info.time_of(start + extrema[swings].0);
+        let depth_cents = extrema.windows(2).map(|pair| (pair[1].1 - pair[0].1).abs()).sum::<f32>() / swings as f32;
+        // Midpoints of the swings, so an odd number of turning points doesn't
+        // pull the centre towards peaks or troughs
+        let centre = extrema.windows(2).map(|pair| (pair[0].1 + pair[1].1) / 2.0).sum::<f32>() / swings as f32;
+
+        Some(Ornament {
+            kind: OrnamentKind::Oscillation {
+                note: nearest_note(centre, tuning)?,
+                rate_hz: swings as f32 / (2.0 * (offset - onset) as f32),
+                depth_cents,
+            },
+            onset,
+            offset,
+        })
+    }
+
+    /// Peaks and troughs of `values` as `(index, cents)`, ignoring wiggles
+    /// smaller than `min_depth_cents`
+    fn extrema(&self, values: &[f32]) -> Vec<(usize, f32)> {
+        let Some(&initial) = values.first() else {
+            return Vec::new();
+        };
+        let mut extrema = Vec::new();
+        let (mut low, mut high) = ((0, initial), (0, initial));
+        // Direction of travel once the first swing is found, and the turning
+        // point it is heading for
+        let mut heading: Option<(bool, (usize, f32))> = None;
+
+        for (index, &value) in values.iter().enumerate().skip(1) {
+            heading = match heading {
+                None => {
+                    if value < low.1 {
+                        low = (index, value);
+                    }
+                    if value > high.1 {
+                        high = (index, value);
+                    }
+                    // Where the run started isn't a turning point, so the
+                    // first swing only sets the direction
+                    if high.1 - low.1 < self.min_depth_cents {
+                        None
+                    } else if low.0 < high.0 {
+                        Some((true, high))
+                    } else {
+                        Some((false, low))
+                    }
+                }
+                Some((rising, turn)) => {
+                    if (value > turn.1) == rising && value != turn.1 {
+                        Some((rising, (index, value)))
+                    } else if (turn.1 - value).abs() >= self.min_depth_cents {
+                        extrema.push(turn);
+                        Some((!rising, (index, value)))
+                    } else {
+                        Some((rising, turn))
+                    }
+                }
+            };
+        }
+        extrema
+    }
+}
+
+/// Indices into a raag's `ornaments` of those heard at least once
+pub fn heard_ornaments(raag: &Raag, ornaments: &[Ornament]) -> Vec<usize> {
+    raag.ornaments
+        .iter()
+        .enumerate()
+        .filter(|(_, pattern)| ornaments.iter().any(|ornament| pattern.matches(ornament)))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Share of a raag's ornaments that were heard, in [0, 1]
+pub fn ornament_score(raag: &Raag, heard: &[usize]) -> f32 {
+    if raag.ornaments.is_empty() {
+        return 0.0;
+    }
+    heard.len() as f32 / raag.ornaments.len() as f32
+}
@@ -0,0 +1,209 @@
+use std::str::FromStr;
+
+use super::{Note, Raag};
+use crate::theory::TuningSystem;
+
+/// Spread of each note in a distribution, in cents (circular Gaussian)
+const SMOOTHING_CENTS: f32 = 20.0;
+
+/// Template weight of a note that appears in the aroha or avaroha
+const SCALE_NOTE_WEIGHT: f32 = 1.0;
+/// Extra template weight of the vadi
+const VADI_WEIGHT: f32 = 1.0;
+/// Extra template weight of the samvadi
+const SAMVADI_WEIGHT: f32 = 0.5;
+/// Scales `PhrasePattern::weight` when phrase notes are added to a template
+const PHRASE_WEIGHT_SCALE: f32 = 0.25;
+
+/// Floor applied to bins before taking logarithms, so empty bins don't blow up KL
+const PROBABILITY_FLOOR: f32 = 1e-6;
+
+/// How two pitch-class distributions are compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DistanceMetric {
+    /// Kullback-Leibler divergence of the template from the observation
+    KlDivergence,
+    /// Bhattacharyya distance, `-ln(sum(sqrt(p * q)))`
+    #[default]
+    Bhattacharyya,
+    /// One minus the cosine similarity
+    Cosine,
+}
+
+impl DistanceMetric {
+    /// Softmax temperature that turns distances under this metric into each
+    /// candidate's share of the ranking. Chosen so that a typical gap between
+    /// a matching and a non-matching template gives a clear but not saturated
+    /// preference. Shares are relative: the same distances give lower shares
+    /// in a larger catalogue.
+    pub fn temperature(&self) -> f32 {
+        match self {
+            DistanceMetric::KlDivergence => 1.0,
+            DistanceMetric::Bhattacharyya => 0.1,
+            DistanceMetric::Cosine => 0.1,
+        }
+    }
+
+    /// Default largest distance between a recording and its best raag at
+    /// which the raag is still reported. Melodies of a raag stay well inside
+    /// it, while one using all twelve swaras evenly falls outside.
+    pub fn default_max_distance(&self) -> f32 {
+        match self {
+            DistanceMetric::KlDivergence => 0.75,
+            DistanceMetric::Bhattacharyya => 0.15,
+            DistanceMetric::Cosine => 0.15,
+        }
+    }
+}
+
+impl FromStr for DistanceMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "kl" | "kl-divergence" | "kullback-leibler" => Ok(Self::KlDivergence),
+            "bhattacharyya" => Ok(Self::Bhattacharyya),
+            "cosine" => Ok(Self::Cosine),
+            _ => Err(format!("Unknown distance metric: {} (expected kl, bhattacharyya or cosine)", s)),
+        }
+    }
+}
+
+/// Normalised histogram over one octave, with bin 0 centred on Sa
+#[derive(Debug, Clone, PartialEq)]
+pub struct PitchClassDistribution {
+    bins: Vec<f32>,
+}
+
+impl PitchClassDistribution {
+    /// Build a distribution from frequency ratios to the tonic (as produced by
+    /// scale-degree analysis). Ratios that are zero or negative are ignored.
+    pub fn from_scale_degrees(ratios: &[f32], bins_per_octave: usize) -> Self {
+        let cents = ratios
+            .iter()
+            .filter(|&&r| r > 0.0)
+            .map(|ratio| (1200.0 * ratio.log2(), 1.0));
+        Self::from_weighted_cents(cents, bins_per_octave)
+    }
+
+    /// Build a distribution from `(cents above Sa, weight)` pairs
+    pub fn from_weighted_cents<I>(observations: I, bins_per_octave: usize) -> Self
+    where
+        I: IntoIterator<Item = (f32, f32)>,
+    {
+        let mut histogram = vec![0.0f32; bins_per_octave.max(1)];
+        for (cents, weight) in observations {
+            let bin = Self::bin_of(cents, histogram.len());
+            histogram[bin] += weight;
+        }
+
+        Self::from_histogram(histogram)
+    }
+
+    /// Build the expected distribution of a raag from its aroha, avaroha,
+    /// vadi, samvadi and characteristic phrases, with notes pitched by
+    /// `tuning` and the raag's own intonation
+    pub fn from_raag(raag: &Raag, bins_per_octave: usize, tuning: &TuningSystem) -> Self {
+        let bins = bins_per_octave.max(1);
+        let tuning = raag.tuning(tuning);
+        let bin_of_note = |note: &Note| Self::bin_of(note.cents(&tuning), bins);
+        let mut histogram = vec![0.0f32; bins];
+
+        // Each scale note counts once, even when it appears in both directions or octaves
+        let mut scale_bins: Vec<usize> = raag
+            .aroha
+            .iter()
+            .chain(&raag.avaroha)
+            .map(bin_of_note)
+            .collect();
+        scale_bins.sort_unstable();
+        scale_bins.dedup();
+        for bin in scale_bins {
+            histogram[bin] += SCALE_NOTE_WEIGHT;
+        }
+
+        histogram[bin_of_note(&raag.vadi)] += VADI_WEIGHT;
+        histogram[bin_of_note(&raag.samvadi)] += SAMVADI_WEIGHT;
+
+        for phrase in &raag.characteristic_phrases {
+            let weight = phrase.weight.max(0.0) * PHRASE_WEIGHT_SCALE / phrase.notes.len().max(1) as f32;
+            for note in &phrase.notes {
+                histogram[bin_of_note(note)] += weight;
+            }
+        }
+
+        Self::from_histogram(histogram)
+    }
+
+    pub fn bins(&self) -> &[f32] {
+        &self.bins
+    }
+
+    /// Mass of the bin containing the given ratio to the tonic
+    pub fn mass_at(&self, ratio: f32) -> f32 {
+        if ratio <= 0.0 {
+            return 0.0;
+        }
+        self.bins[Self::bin_of(1200.0 * ratio.log2(), self.bins.len())]
+    }
+
+    /// True if the distribution was built from no data at all
+    pub fn is_empty(&self) -> bool {
+        self.bins.iter().all(|&b| b == 0.0)
+    }
+
+    /// Distance to another distribution of the same resolution (lower is closer)
+    pub fn distance(&self, template: &PitchClassDistribution, metric: DistanceMetric) -> f32 {
+        let pairs = self.bins.iter().zip(&template.bins);
+
+        match metric {
+            DistanceMetric::KlDivergence => pairs
+                .filter(|(&p, _)| p > 0.0)
+                .map(|(&p, &q)| p * (p / q.max(PROBABILITY_FLOOR)).ln())
+                .sum(),
+            DistanceMetric::Bhattacharyya => {
+                let coefficient: f32 = pairs.map(|(&p, &q)| (p * q).sqrt()).sum();
+                -coefficient.max(PROBABILITY_FLOOR).ln()
+            }
+            DistanceMetric::Cosine => {
+                let dot: f32 = pairs.map(|(&p, &q)| p * q).sum();
+                let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+                let denominator = norm(&self.bins) * norm(&template.bins);
+                if denominator > 0.0 {
+                    1.0 - dot / denominator
+                } else {
+                    1.0
+                }
+            }
+        }
+    }
+
+    fn bin_of(cents: f32, bins: usize) -> usize {
+        let position = cents.rem_euclid(1200.0) * bins as f32 / 1200.0;
+        (position.round() as usize) % bins
+    }
+
+    /// Smooth circularly and normalise to unit mass
+    fn from_histogram(histogram: Vec<f32>) -> Self {
+        let bins = histogram.len();
+        let bin_cents = 1200.0 / bins as f32;
+
+        let mut smoothed = vec![0.0f32; bins];
+        for (source, &mass) in histogram.iter().enumerate().filter(|(_, &m)| m > 0.0) {
+            for (target, value) in smoothed.iter_mut().enumerate() {
+                let steps = source.abs_diff(target).min(bins - source.abs_diff(target));
+                let cents = steps as f32 * bin_cents;
+                *value += mass * (-0.5 * (cents / SMOOTHING_CENTS).powi(2)).exp();
+            }
+        }
+
+        let total: f32 = smoothed.iter().sum();
+        if total > 0.0 {
+            for value in &mut smoothed {
+                *value /= total;
+            }
+        }
+
+        Self { bins: smoothed }
+    }
+}
@@ -0,0 +1,209 @@
+use serde::Serialize;
+
+use super::{Note, Raag, TranscribedNote};
+use crate::theory::TuningSystem;
+
+/// Octave transpositions a phrase is searched at, in cents
+const OCTAVE_SHIFTS: [f32; 3] = [-1200.0, 0.0, 1200.0];
+
+/// One occurrence of a characteristic phrase in the recording
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PhraseMatch {
+    /// Index into the raag's `characteristic_phrases`
+    pub phrase: usize,
+    /// Start of the first matched note, in seconds
+    pub start: f64,
+    /// End of the last matched note, in seconds
+    pub end: f64,
+    /// Alignment quality in [0, 1]; 1 means every note matched exactly
+    pub score: f32,
+}
+
+/// Finds characteristic phrases in a transcription with subsequence dynamic
+/// time warping over cents. Working on transcribed notes rather than frames
+/// makes the search independent of tempo; the skip penalty lets ornaments
+/// and passing notes sit between the phrase notes.
+#[derive(Debug, Clone, Copy)]
+pub struct PhraseSpotter {
+    /// Pitch difference at which a note pair costs the maximum of 1
+    pub max_cost_cents: f32,
+    /// Extra cost of a performed note with no phrase note, or the reverse
+    pub skip_penalty: f32,
+    /// Minimum score of a reported match
+    pub threshold: f32,
+}
+
+impl Default for PhraseSpotter {
+    fn default() -> Self {
+        Self {
+            max_cost_cents: 100.0,
+            skip_penalty: 0.3,
+            threshold: 0.75,
+        }
+    }
+}
+
+/// Cell of the alignment matrix: summed cost, path length and first target note
+#[derive(Debug, Clone, Copy)]
+struct Cell {
+    cost: f32,
+    length: usize,
+    start: usize,
+}
+
+impl Cell {
+    fn extend(self, cost: f32) -> Cell {
+        Cell {
+            cost: self.cost + cost,
+            length: self.length + 1,
+            start: self.start,
+        }
+    }
+}
+
+impl PhraseSpotter {
+    /// Non-overlapping occurrences of every phrase of `raag` in a
+    /// transcription, in order of time
+    pub fn spot(&self, raag: &Raag, tuning: &TuningSystem, transcription: &[TranscribedNote]) -> Vec<PhraseMatch> {
+        let tuning = raag.tuning(tuning);
+        let notes = Self::target(transcription);
+        let mut matches: Vec<PhraseMatch> = raag
+            .characteristic_phrases
+            .iter()
+            .enumerate()
+            .flat_map(|(phrase, pattern)| {
+                let query = Self::query(&pattern.notes, &tuning);
+                let notes = &notes;
+                self.find(&query, notes)
+                    .into_iter()
+                    .map(move |(first, last, score)| PhraseMatch {
+                        phrase,
+                        start: notes[first].onset,
+                        end: notes[last].offset,
+                        score,
+                    })
+            })
+            .collect();
+        matches.sort_by(|a, b| a.start.total_cmp(&b.start));
+        matches
+    }
+
+    /// Occurrences of `query` (cents above Sa) in `notes` as `(first note,
+    /// last note, score)`, best first and without overlaps
+    pub fn find(&self, query: &[f32], notes: &[TranscribedNote]) -> Vec<(usize, usize, f32)> {
+        if query.is_empty() || notes.is_empty() {
+            return Vec::new();
+        }
+
+        let mut candidates: Vec<(usize, usize, f32)> = OCTAVE_SHIFTS
+            .iter()
+            .flat_map(|&shift| self.align(query, notes, shift))
+            .filter(|&(_, _, score)| score >= self.threshold)
+            .collect();
+        candidates.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+        let mut chosen: Vec<(usize, usize, f32)> = Vec::new();
+        for candidate in candidates {
+            let overlaps = chosen
+                .iter()
+                .any(|&(first, last, _)| candidate.0 <= last && first <= candidate.1);
+            if !overlaps {
+                chosen.push(candidate);
+            }
+        }
+        chosen
+    }
+
+    /// Best alignment of `query` transposed by `shift` cents ending at each note
+    fn align(&self, query: &[f32], notes: &[TranscribedNote], shift: f32) -> Vec<(usize, usize, f32)> {
+        let cost = |i: usize, j: usize| {
+            (query[i] + shift - notes[j].cents).abs().min(self.max_cost_cents) / self.max_cost_cents
+        };
+
+        // Free start: the phrase may begin at any note
+        let mut previous: Vec<Cell> = (0..notes.len())
+            .map(|j| Cell {
+                cost: cost(0, j),
+                length: 1,
+                start: j,
+            })
+            .collect();
+
+        for i in 1..query.len() {
+            let mut row: Vec<Cell> = Vec::with_capacity(notes.len());
+            for j in 0..notes.len() {
+                let c = cost(i, j);
+                // Phrase note with no performed note of its own
+                let mut best = previous[j].extend(c + self.skip_penalty);
+                if j > 0 {
+                    let steps = [
+                        previous[j - 1].extend(c),
+                        // Performed note with no phrase note, e.g. a kan or passing note
+                        row[j - 1].extend(c + self.skip_penalty),
+                    ];
+                    for step in steps {
+                        if step.cost < best.cost {
+                            best = step;
+                        }
+                    }
+                }
+                row.push(best);
+            }
+            previous = row;
+        }
+
+        // Free end: the phrase may finish at any note
+        previous
+            .iter()
+            .enumerate()
+            .map(|(last, cell)| (cell.start, last, (1.0 - cell.cost / cell.length as f32).max(0.0)))
+            .collect()
+    }
+
+    /// Transcription with repeated notes merged, since a phrase names each
+    /// note once however often it is re-sung
+    fn target(transcription: &[TranscribedNote]) -> Vec<TranscribedNote> {
+        let mut notes: Vec<TranscribedNote> = Vec::with_capacity(transcription.len());
+        for &note in transcription {
+            match notes.last_mut() {
+                Some(last) if last.note == note.note => {
+                    last.offset = note.offset;
+                    last.end = note.end;
+                }
+                _ => notes.push(note),
+            }
+        }
+        notes
+    }
+
+    /// Cents of the phrase notes, with repeated notes collapsed
+    fn query(notes: &[Note], tuning: &TuningSystem) -> Vec<f32> {
+        let mut query: Vec<f32> = notes.iter().map(|note| note.cents(tuning)).collect();
+        query.dedup();
+        query
+    }
+}
+
+/// Weighted share of a raag's phrases that were heard, in [0, 1]: each phrase
+/// counts its best match score times its weight
+pub fn phrase_score(raag: &Raag, matches: &[PhraseMatch]) -> f32 {
+    let total: f32 = raag.characteristic_phrases.iter().map(|phrase| phrase.weight.max(0.0)).sum();
+    if total <= 0.0 {
+        return 0.0;
+    }
+
+    let heard: f32 = raag
+        .characteristic_phrases
+        .iter()
+        .enumerate()
+        .map(|(index, phrase)| {
+            let best = matches
+                .iter()
+                .filter(|m| m.phrase == index)
+                .map(|m| m.score)
+                .fold(0.0, f32::max);
+            phrase.weight.max(0.0) * best
+        })
+        .sum();
+    heard / total
+}
@@ -1,13 +1,76 @@
+//! Raag definitions and the database the classifier matches against.
+//!
+//! Definitions are loaded from JSON or TOML. A file holds a list of raags
+//! under the key `raags`; each raag has these fields:
+//!
+//! | field                    | type            | meaning                                   |
+//! |--------------------------|-----------------|-------------------------------------------|
+//! | `name`                   | string          | unique name, e.g. `"Yaman"`               |
+//! | `aliases`                | list of strings | optional; other names, e.g. `["Kalyan"]`  |
+//! | `thaat`                  | string          | parent thaat, e.g. `"Kalyan"`             |
+//! | `aroha`                  | list of notes   | ascending scale                           |
+//! | `avaroha`                | list of notes   | descending scale                          |
+//! | `vadi`                   | note            | most important note                       |
+//! | `samvadi`                | note            | second most important note                |
+//! | `characteristic_phrases` | list of phrases | optional; pakad and other typical phrases |
+//! | `intonation`             | table           | optional; swaras tuned differently        |
+//! | `ornaments`              | list            | optional; ornaments typical of the raag   |
+//!
+//! A note is a string in sargam or Bhatkhande notation (see
+//! [`Notation`](crate::theory::Notation)): `"S"`, `"r"` for komal Re, `"M"`
+//! for tivra Ma, `".N"` for mandra Ni, `"S'"` for taar Sa, or names such as
+//! `"komal Re"`. A list of notes may be written as one space-separated string.
+//! The older `{ "name": "Re", "frequency_ratio": 1.125 }` form is still
+//! accepted and snapped to the nearest swara. A phrase is `{ notes, weight }`,
+//! with `weight` giving its importance for the raag.
+//!
+//! Notes take their pitch from the classifier's [`TuningSystem`]. A raag that
+//! bends some swaras away from it lists them under `intonation`, each as a
+//! shruti name or in cents above Sa, e.g. Darbari's ati-komal Ga as
+//! `{ "g": "Dayavati" }` or `{ "g": 290 }`.
+//!
+//! Each entry of `ornaments` names a `kind` and the swaras it falls on, in
+//! any octave: `{ "kind": "oscillation", "swara": "r", "max_rate_hz": 3 }`
+//! for an andolan (the rate limit is optional), `{ "kind": "meend", "from":
+//! "m", "to": "g" }` for a glide, or `{ "kind": "kan", "grace": "m", "swara":
+//! "g" }` for a grace note touched before a swara.
+//!
+//! ```json
+//! {
+//!   "raags": [
+//!     {
+//!       "name": "Bhupali",
+//!       "thaat": "Kalyan",
+//!       "aroha": "S R G P D S'",
+//!       "avaroha": "S' D P G R S",
+//!       "vadi": "G",
+//!       "samvadi": "D",
+//!       "characteristic_phrases": [{ "notes": "G R S .D", "weight": 0.8 }]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! The same structure in TOML uses `[[raags]]` tables. The bundled catalogue
+//! in `data/raags.json` is a complete example, covering all ten thaats.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::ornament::OrnamentPattern;
+use crate::error::{RaagError, Result};
+use crate::theory::swara::note_list;
+use crate::theory::{Intonation, Swara, TuningSystem};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Note {
-    pub name: String,
-    pub frequency_ratio: f32, // Ratio to Sa (tonic)
-}
+pub use crate::theory::Note;
+
+/// Raag definitions compiled into the library
+const BUNDLED_RAAGS: &str = include_str!("../../data/raags.json");
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PhrasePattern {
+    #[serde(with = "note_list")]
     pub notes: Vec<Note>,
     pub weight: f32, // Importance of this phrase for the raag
 }
@@ -15,12 +78,41 @@ pub struct PhrasePattern {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Raag {
     pub name: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,       // Other names the raag is known by
+    #[serde(with = "note_list")]
     pub aroha: Vec<Note>,           // Ascending scale
+    #[serde(with = "note_list")]
     pub avaroha: Vec<Note>,         // Descending scale
     pub vadi: Note,                 // Most important note
     pub samvadi: Note,              // Second most important note
+    #[serde(default)]
     pub characteristic_phrases: Vec<PhrasePattern>,
     pub thaat: String,              // Parent scale
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub intonation: BTreeMap<Swara, Intonation>, // Swaras tuned away from the tuning system
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ornaments: Vec<OrnamentPattern>, // Characteristic ornaments
+}
+
+impl Raag {
+    /// True if `name` is this raag's name or one of its aliases, ignoring case
+    pub fn is_named(&self, name: &str) -> bool {
+        std::iter::once(&self.name)
+            .chain(&self.aliases)
+            .any(|candidate| candidate.eq_ignore_ascii_case(name))
+    }
+
+    /// `base` with this raag's own intonation applied
+    pub fn tuning(&self, base: &TuningSystem) -> TuningSystem {
+        base.with_intonation(&self.intonation)
+    }
+}
+
+/// On-disk layout of a raag definition file
+#[derive(Debug, Deserialize)]
+struct RaagFile {
+    raags: Vec<Raag>,
 }
 
 pub struct RaagDatabase {
@@ -28,96 +120,95 @@ pub struct RaagDatabase {
 }
 
 impl RaagDatabase {
+    /// The bundled catalogue
     pub fn new() -> Self {
-        Self {
-            raags: Self::initialize_common_raags(),
+        Self::from_json_str(BUNDLED_RAAGS).expect("bundled raag definitions are valid")
+    }
+
+    pub fn from_raags(raags: Vec<Raag>) -> Self {
+        Self { raags }
+    }
+
+    /// Load definitions from a `.json` or `.toml` file. Definitions are
+    /// validated (see `validate`) and rejected if they contain errors.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        let extension = path.extension().and_then(|e| e.to_str()).map(str::to_lowercase);
+
+        let parsed = match extension.as_deref() {
+            Some("json") => Self::from_json_str(&contents),
+            Some("toml") => Self::from_toml_str(&contents),
+            _ => Err(RaagError::InvalidRaagDefinitions("expected a .json or .toml file".to_string())),
+        };
+
+        parsed.map_err(|e| match e {
+            RaagError::InvalidRaagDefinitions(message) => {
+                RaagError::InvalidRaagDefinitions(format!("{}: {}", path.display(), message))
+            }
+            other => other,
+        })
+    }
+
+    pub fn from_json_str(json: &str) -> Result<Self> {
+        let file: RaagFile =
+            serde_json::from_str(json).map_err(|e| RaagError::InvalidRaagDefinitions(e.to_string()))?;
+        Self::validated(file.raags)
+    }
+
+    pub fn from_toml_str(toml: &str) -> Result<Self> {
+        let file: RaagFile = toml::from_str(toml).map_err(|e| RaagError::InvalidRaagDefinitions(e.to_string()))?;
+        Self::validated(file.raags)
+    }
+
+    /// Reject definitions with validation errors; warnings are left for the
+    /// caller to fetch with `validate`
+    fn validated(raags: Vec<Raag>) -> Result<Self> {
+        let database = Self::from_raags(raags);
+        let errors: Vec<_> = database.validate().into_iter().filter(|d| d.is_error()).collect();
+        if errors.is_empty() {
+            Ok(database)
+        } else {
+            Err(RaagError::RaagValidation(errors))
         }
     }
 
+    /// Add the raags of `other`. A raag with the same name as an existing one
+    /// replaces it, so a file can both extend and correct the catalogue.
+    pub fn merge(&mut self, other: RaagDatabase) {
+        for raag in other.raags {
+            match self.raags.iter_mut().find(|existing| existing.name == raag.name) {
+                Some(existing) => *existing = raag,
+                None => self.raags.push(raag),
+            }
+        }
+    }
+
+    /// `merge`, then validate the result, so that names and aliases clashing
+    /// across the two databases are caught as well as errors in either
+    pub fn merge_validated(mut self, other: RaagDatabase) -> Result<Self> {
+        self.merge(other);
+        Self::validated(self.raags)
+    }
+
     pub fn get_raags(&self) -> &[Raag] {
         &self.raags
     }
 
+    /// Look a raag up by name or alias, ignoring case
     pub fn find_raag(&self, name: &str) -> Option<&Raag> {
-        self.raags.iter().find(|r| r.name == name)
+        self.raags.iter().find(|r| r.is_named(name))
     }
 
-    fn initialize_common_raags() -> Vec<Raag> {
-        vec![
-            // Raag Yaman
-            Raag {
-                name: "Yaman".to_string(),
-                aroha: vec![
-                    Note { name: "Sa".to_string(), frequency_ratio: 1.0 },
-                    Note { name: "Re".to_string(), frequency_ratio: 9.0/8.0 },
-                    Note { name: "Ga".to_string(), frequency_ratio: 5.0/4.0 },
-                    Note { name: "Ma#".to_string(), frequency_ratio: 45.0/32.0 },
-                    Note { name: "Pa".to_string(), frequency_ratio: 3.0/2.0 },
-                    Note { name: "Dha".to_string(), frequency_ratio: 27.0/16.0 },
-                    Note { name: "Ni".to_string(), frequency_ratio: 15.0/8.0 },
-                ],
-                avaroha: vec![
-                    Note { name: "Sa".to_string(), frequency_ratio: 2.0 },
-                    Note { name: "Ni".to_string(), frequency_ratio: 15.0/8.0 },
-                    Note { name: "Dha".to_string(), frequency_ratio: 27.0/16.0 },
-                    Note { name: "Pa".to_string(), frequency_ratio: 3.0/2.0 },
-                    Note { name: "Ma#".to_string(), frequency_ratio: 45.0/32.0 },
-                    Note { name: "Ga".to_string(), frequency_ratio: 5.0/4.0 },
-                    Note { name: "Re".to_string(), frequency_ratio: 9.0/8.0 },
-                    Note { name: "Sa".to_string(), frequency_ratio: 1.0 },
-                ],
-                vadi: Note { name: "Ga".to_string(), frequency_ratio: 5.0/4.0 },
-                samvadi: Note { name: "Ni".to_string(), frequency_ratio: 15.0/8.0 },
-                characteristic_phrases: vec![],
-                thaat: "Kalyan".to_string(),
-            },
-            // Raag Bhairavi
-            Raag {
-                name: "Bhairavi".to_string(),
-                aroha: vec![
-                    Note { name: "Sa".to_string(), frequency_ratio: 1.0 },
-                    Note { name: "Re♭".to_string(), frequency_ratio: 16.0/15.0 },
-                    Note { name: "Ga♭".to_string(), frequency_ratio: 6.0/5.0 },
-                    Note { name: "Ma".to_string(), frequency_ratio: 4.0/3.0 },
-                    Note { name: "Pa".to_string(), frequency_ratio: 3.0/2.0 },
-                    Note { name: "Dha♭".to_string(), frequency_ratio: 8.0/5.0 },
-                    Note { name: "Ni♭".to_string(), frequency_ratio: 16.0/9.0 },
-                ],
-                avaroha: vec![
-                    Note { name: "Sa".to_string(), frequency_ratio: 2.0 },
-                    Note { name: "Ni♭".to_string(), frequency_ratio: 16.0/9.0 },
-                    Note { name: "Dha♭".to_string(), frequency_ratio: 8.0/5.0 },
-                    Note { name: "Pa".to_string(), frequency_ratio: 3.0/2.0 },
-                    Note { name: "Ma".to_string(), frequency_ratio: 4.0/3.0 },
-                    Note { name: "Ga♭".to_string(), frequency_ratio: 6.0/5.0 },
-                    Note { name: "Re♭".to_string(), frequency_ratio: 16.0/15.0 },
-                    Note { name: "Sa".to_string(), frequency_ratio: 1.0 },
-                ],
-                vadi: Note { name: "Ma".to_string(), frequency_ratio: 4.0/3.0 },
-                samvadi: Note { name: "Sa".to_string(), frequency_ratio: 1.0 },
-                characteristic_phrases: vec![
-                    PhrasePattern {
-                        notes: vec![
-                            Note { name: "Dha♭".to_string(), frequency_ratio: 8.0/5.0 },
-                            Note { name: "Ma".to_string(), frequency_ratio: 4.0/3.0 },
-                            Note { name: "Ga♭".to_string(), frequency_ratio: 6.0/5.0 },
-                            Note { name: "Re♭".to_string(), frequency_ratio: 16.0/15.0 },
-                            Note { name: "Sa".to_string(), frequency_ratio: 1.0 },
-                        ],
-                        weight: 0.8, // High importance - characteristic descending phrase
-                    },
-                    PhrasePattern {
-                        notes: vec![
-                            Note { name: "Sa".to_string(), frequency_ratio: 1.0 },
-                            Note { name: "Ga♭".to_string(), frequency_ratio: 6.0/5.0 },
-                            Note { name: "Ma".to_string(), frequency_ratio: 4.0/3.0 },
-                        ],
-                        weight: 0.7, // Pakad phrase
-                    },
-                ],
-                thaat: "Bhairavi".to_string(),
-            },
-            // Add more raags here as needed
-        ]
+    /// Like `find_raag`, but reports a missing raag as an error
+    pub fn get_raag(&self, name: &str) -> Result<&Raag> {
+        self.find_raag(name)
+            .ok_or_else(|| RaagError::UnknownRaag(name.to_string()))
     }
-}
\ No newline at end of file
+}
+
+impl Default for RaagDatabase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
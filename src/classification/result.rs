@@ -0,0 +1,78 @@
+use serde::Serialize;
+
+use super::{Ornament, PhraseMatch, TonicEstimate, Transcription};
+
+/// Why a candidate raag scored the way it did
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MatchEvidence {
+    /// Share of the recording's pitch distribution on the raag's vadi
+    pub vadi_strength: f32,
+    /// Share of the recording's pitch distribution on the raag's samvadi
+    pub samvadi_strength: f32,
+    /// Occurrences of the raag's `characteristic_phrases`, in order of time
+    pub phrase_matches: Vec<PhraseMatch>,
+    /// Weighted share of the raag's phrases that were heard, in [0, 1]
+    pub phrase_score: f32,
+    /// Indices into the raag's `ornaments` of those heard at least once
+    pub ornament_matches: Vec<usize>,
+    /// Share of the raag's ornaments that were heard, in [0, 1]
+    pub ornament_score: f32,
+    /// Distance of the ascending movements from the aroha, if there were any
+    pub aroha_distance: Option<f32>,
+    /// Distance of the descending movements from the avaroha, if there were any
+    pub avaroha_distance: Option<f32>,
+}
+
+/// One raag considered by the classifier
+#[derive(Debug, Clone, Serialize)]
+pub struct RaagCandidate {
+    pub name: String,
+    /// Calibrated confidence that the recording is in this raag, in [0, 1]:
+    /// 1 at distance 0, falling to 0 at the classifier's maximum distance. It
+    /// depends only on this raag, not on what else is in the catalogue.
+    pub score: f32,
+    /// Share of the ranking among all candidates, in [0, 1], with phrases and
+    /// ornaments heard taken into account. Shares sum to 1, so they spread
+    /// thinner as the catalogue grows; candidates are ordered by it.
+    pub share: f32,
+    /// Raw distance between the recording and the raag's template (lower is closer)
+    pub distance: f32,
+    pub evidence: MatchEvidence,
+}
+
+/// Full output of `RaagClassifier::classify`
+#[derive(Debug, Clone, Serialize)]
+pub struct ClassificationResult {
+    /// Every raag in the database, best match first
+    pub candidates: Vec<RaagCandidate>,
+    /// Tonic (Sa) the recording was analysed against
+    pub tonic: TonicEstimate,
+    /// Steady notes of the recording as swaras relative to the tonic
+    pub transcription: Transcription,
+    /// Meends, oscillations and kans, in order of onset
+    pub ornaments: Vec<Ornament>,
+    /// True when even the best candidate's distance exceeded the classifier's
+    /// maximum, so no raag fits
+    pub abstained: bool,
+}
+
+impl ClassificationResult {
+    /// The winning raag, or `None` if the classifier abstained
+    pub fn best(&self) -> Option<&RaagCandidate> {
+        if self.abstained {
+            None
+        } else {
+            self.candidates.first()
+        }
+    }
+
+    /// Name of the winning raag, or `None` if the classifier abstained
+    pub fn raag(&self) -> Option<&str> {
+        self.best().map(|candidate| candidate.name.as_str())
+    }
+
+    /// The `k` highest-ranked candidates, regardless of abstention
+    pub fn top(&self, k: usize) -> &[RaagCandidate] {
+        &self.candidates[..k.min(self.candidates.len())]
+    }
+}
@@ -0,0 +1,384 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::error::{RaagError, Result};
+use crate::features::{LongTermSpectrum, PitchFrame};
+
+/// Resolution of the folded salience histograms, in cents
+const BIN_CENTS: f32 = 10.0;
+const BINS: usize = (1200.0 / BIN_CENTS) as usize;
+/// Spread of each pitch observation in the histograms, in cents
+const SMOOTHING_CENTS: f32 = 15.0;
+
+/// Interval from Sa to Pa and from Sa to Ma, in cents
+const PA_CENTS: f32 = 702.0;
+const MA_CENTS: f32 = 498.0;
+/// Weight of the Pa peak when scoring a Sa candidate
+const FIFTH_WEIGHT: f32 = 0.4;
+/// Ma stands in for Pa in raags that omit it, but counts for less
+const FOURTH_WEIGHT: f32 = 0.6;
+/// Share of the salience taken from the drone when one is present
+const DRONE_WEIGHT: f32 = 0.5;
+
+/// Pitch frames whose neighbours stay within this many cents count as held notes
+const STABLE_CENTS: f32 = 30.0;
+/// Weight of frames that are part of a glide rather than a held note
+const UNSTABLE_WEIGHT: f32 = 0.25;
+/// Only pitches within this range feed the pitch histogram (Hz)
+const MIN_PITCH: f32 = 60.0;
+const MAX_PITCH: f32 = 1200.0;
+
+/// Spectral peaks considered for drone analysis (Hz)
+const MIN_DRONE_HZ: f32 = 60.0;
+const MAX_DRONE_HZ: f32 = 2000.0;
+/// A spectral peak must exceed the median magnitude by this factor
+const DRONE_PROMINENCE: f32 = 4.0;
+/// ...and be present almost throughout (see `LongTermSpectrum::steadiness`)
+const DRONE_STEADINESS: f32 = 0.5;
+
+/// Observations within this many cents of the winning bin refine its position
+const REFINE_CENTS: f32 = 30.0;
+/// Voiced frames within this many cents of a Sa candidate count towards its octave
+const OCTAVE_MATCH_CENTS: f32 = 50.0;
+
+/// Octave of note names given without one, e.g. `C#` means C#3
+const DEFAULT_NOTE_OCTAVE: i32 = 3;
+/// Plausible range of a user-supplied tonic (Hz)
+const MIN_TONIC_HZ: f32 = 20.0;
+const MAX_TONIC_HZ: f32 = 2000.0;
+
+/// Where a tonic came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum TonicSource {
+    /// Peaks of the melody's pitch histogram
+    PitchHistogram,
+    /// Pitch histogram combined with sustained drone peaks in the spectrum
+    PitchAndDrone,
+    /// Supplied by the user, not estimated
+    UserSupplied,
+}
+
+impl fmt::Display for TonicSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TonicSource::PitchHistogram => "pitch histogram",
+            TonicSource::PitchAndDrone => "pitch histogram and drone",
+            TonicSource::UserSupplied => "user supplied",
+        })
+    }
+}
+
+/// Estimated (or supplied) Sa
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TonicEstimate {
+    /// Frequency of Sa in Hz, at cent resolution
+    pub frequency: f32,
+    /// How clearly the winning candidate beat the runner-up, in [0, 1]
+    pub confidence: f32,
+    pub source: TonicSource,
+}
+
+impl TonicEstimate {
+    pub fn user_supplied(frequency: f32) -> Self {
+        Self {
+            frequency,
+            confidence: 1.0,
+            source: TonicSource::UserSupplied,
+        }
+    }
+}
+
+/// Parse a user-supplied tonic: a frequency in Hz (`138.6`, `138.6Hz`) or a
+/// Western note name (`C#`, `Db3`), optionally followed by a cent offset
+/// ending in `c` (`C#+25c`, `146.8-10c`). Without the `c`, a signed number
+/// after a note is its octave, so `C#-1` is C# in octave -1 rather than one
+/// cent below C#. Note names without an octave are placed in octave 3 (C3 =
+/// 130.8 Hz), where most Sa settings fall.
+pub fn parse_tonic(s: &str) -> std::result::Result<f32, String> {
+    let spec = s.trim();
+    let invalid = || format!("Invalid tonic: {} (expected Hz like 138.6, or a note like C# or C#+25c)", s);
+
+    // The last sign after the first character starts the cent offset
+    let offset = spec
+        .strip_suffix(['c', 'C'])
+        .and_then(|rest| rest.rfind(['+', '-']).filter(|&split| split > 0).map(|split| (rest, split)));
+    let (base, cents) = match offset {
+        Some((rest, split)) => (&rest[..split], rest[split..].parse::<f32>().map_err(|_| invalid())?),
+        None => (spec, 0.0),
+    };
+
+    let base = base.trim();
+    let frequency = match base.trim_end_matches("Hz").trim_end_matches("hz").trim().parse::<f32>() {
+        Ok(hz) => hz,
+        Err(_) => note_frequency(base).ok_or_else(invalid)?,
+    } * 2f32.powf(cents / 1200.0);
+
+    if frequency.is_finite() && (MIN_TONIC_HZ..=MAX_TONIC_HZ).contains(&frequency) {
+        Ok(frequency)
+    } else {
+        Err(format!("Tonic out of range: {} ({:.1} Hz, expected {}-{} Hz)", s, frequency, MIN_TONIC_HZ, MAX_TONIC_HZ))
+    }
+}
+
+/// Frequency of a note like `C`, `C#`, `Db` or `F#2` (A4 = 440 Hz)
+fn note_frequency(name: &str) -> Option<f32> {
+    let mut chars = name.chars();
+    let letter = chars.next()?.to_ascii_uppercase();
+    let mut semitone: i32 = match letter {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+
+    let rest = chars.as_str();
+    let octave = match rest.chars().next() {
+        Some('#') => {
+            semitone += 1;
+            &rest[1..]
+        }
+        Some('b') => {
+            semitone -= 1;
+            &rest[1..]
+        }
+        _ => rest,
+    };
+    let octave: i32 = match octave {
+        "" => DEFAULT_NOTE_OCTAVE,
+        // A plus sign is a cent offset missing its `c`, not an octave
+        signed if signed.starts_with('+') => return None,
+        octave => octave.parse().ok()?,
+    };
+
+    let midi = 12 * (octave + 1) + semitone;
+    Some(440.0 * 2f32.powf((midi - 69) as f32 / 12.0))
+}
+
+/// Finds Sa from the pitch contour and, when available, the drone.
+///
+/// Both sources are folded into one octave at 10-cent resolution. Each bin is
+/// scored as a Sa candidate by its own salience plus that of the Pa a fifth
+/// above (or Ma a fourth above, for raags that omit Pa), which separates Sa from
+/// a heavily used vadi or nyas note. Folding merges Sa and Sa', so both count
+/// for the same candidate. The winner is then refined to cent precision and
+/// placed in the octave given by `min_frequency..max_frequency`.
+#[derive(Debug, Clone, Copy)]
+pub struct TonicEstimator {
+    pub min_frequency: f32,
+    pub max_frequency: f32,
+}
+
+impl Default for TonicEstimator {
+    fn default() -> Self {
+        Self {
+            min_frequency: 90.0,
+            max_frequency: 360.0,
+        }
+    }
+}
+
+impl TonicEstimator {
+    pub fn estimate(&self, pitch_contour: &[PitchFrame], spectrum: Option<&LongTermSpectrum>) -> Result<TonicEstimate> {
+        let pitch_salience = Self::pitch_salience(pitch_contour).ok_or(RaagError::NoVoicedFrames)?;
+        let drone_salience = spectrum.and_then(Self::drone_salience);
+
+        let (salience, source) = match &drone_salience {
+            Some(drone) => (
+                pitch_salience
+                    .iter()
+                    .zip(drone)
+                    .map(|(p, d)| (1.0 - DRONE_WEIGHT) * p + DRONE_WEIGHT * d)
+                    .collect(),
+                TonicSource::PitchAndDrone,
+            ),
+            None => (pitch_salience, TonicSource::PitchHistogram),
+        };
+
+        let at = |cents: f32| salience[bin_of(cents)];
+        let scores: Vec<f32> = (0..BINS)
+            .map(|bin| {
+                let cents = bin as f32 * BIN_CENTS;
+                let support = at(cents + PA_CENTS).max(FOURTH_WEIGHT * at(cents + MA_CENTS));
+                at(cents) + FIFTH_WEIGHT * support
+            })
+            .collect();
+
+        let best = (0..BINS).max_by(|&a, &b| scores[a].total_cmp(&scores[b])).unwrap_or(0);
+
+        // Runner-up: best candidate at least a semitone away from the winner
+        let runner_up = (0..BINS)
+            .filter(|&bin| circular_distance(bin, best) as f32 * BIN_CENTS >= 100.0)
+            .map(|bin| scores[bin])
+            .fold(0.0f32, f32::max);
+        let confidence = if scores[best] > 0.0 {
+            ((scores[best] - runner_up) / scores[best]).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        let candidate = best as f32 * BIN_CENTS;
+        let cents = spectrum
+            .filter(|_| drone_salience.is_some())
+            .and_then(|spectrum| Self::drone_sa(spectrum, candidate))
+            .unwrap_or_else(|| Self::refine(pitch_contour, candidate));
+        Ok(TonicEstimate {
+            frequency: self.place_in_range(pitch_contour, cents),
+            confidence,
+            source,
+        })
+    }
+
+    /// Folded, smoothed histogram of voiced pitches, weighted towards held notes
+    fn pitch_salience(pitch_contour: &[PitchFrame]) -> Option<Vec<f32>> {
+        let cents: Vec<Option<f32>> = pitch_contour
+            .iter()
+            .map(|frame| {
+                (frame.is_voiced() && frame.frequency > MIN_PITCH && frame.frequency < MAX_PITCH)
+                    .then(|| cents_from_a440(frame.frequency))
+            })
+            .collect();
+
+        let mut histogram = vec![0.0f32; BINS];
+        for (i, value) in cents.iter().enumerate() {
+            let Some(value) = *value else { continue };
+            let steady = |neighbour: Option<&Option<f32>>| {
+                matches!(neighbour, Some(Some(other)) if (other - value).abs() < STABLE_CENTS)
+            };
+            let stable = steady(i.checked_sub(1).and_then(|j| cents.get(j))) && steady(cents.get(i + 1));
+            let weight = pitch_contour[i].voicing.max(0.05) * if stable { 1.0 } else { UNSTABLE_WEIGHT };
+            histogram[bin_of(value)] += weight;
+        }
+
+        normalise_peak(smooth(&histogram))
+    }
+
+    /// Folded, smoothed salience of the sustained peaks in the long-term
+    /// spectrum, or `None` if nothing in it sounds like a drone
+    fn drone_salience(spectrum: &LongTermSpectrum) -> Option<Vec<f32>> {
+        let mut histogram = vec![0.0f32; BINS];
+        for (frequency, magnitude) in spectrum.peaks(MIN_DRONE_HZ, MAX_DRONE_HZ, DRONE_PROMINENCE) {
+            let steadiness = spectrum.steadiness(frequency);
+            if steadiness >= DRONE_STEADINESS {
+                histogram[bin_of(cents_from_a440(frequency))] += magnitude.sqrt() * steadiness;
+            }
+        }
+
+        normalise_peak(smooth(&histogram))
+    }
+
+    /// Folded position of the strongest steady drone peak near a candidate, in cents
+    fn drone_sa(spectrum: &LongTermSpectrum, candidate: f32) -> Option<f32> {
+        spectrum
+            .peaks(MIN_DRONE_HZ, MAX_DRONE_HZ, DRONE_PROMINENCE)
+            .into_iter()
+            .filter(|&(frequency, _)| spectrum.steadiness(frequency) >= DRONE_STEADINESS)
+            .map(|(frequency, magnitude)| (folded_offset(cents_from_a440(frequency), candidate), magnitude))
+            .filter(|(offset, _)| offset.abs() <= REFINE_CENTS)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(offset, _)| candidate + offset)
+    }
+
+    /// Mean folded position of the voiced pitches near a candidate, in cents
+    fn refine(pitch_contour: &[PitchFrame], candidate: f32) -> f32 {
+        let mut sum = 0.0f64;
+        let mut weight = 0.0f64;
+        for frame in pitch_contour.iter().filter(|f| f.is_voiced()) {
+            let offset = folded_offset(cents_from_a440(frame.frequency), candidate);
+            if offset.abs() <= REFINE_CENTS {
+                sum += offset as f64 * frame.voicing as f64;
+                weight += frame.voicing as f64;
+            }
+        }
+
+        if weight > 0.0 {
+            candidate + (sum / weight) as f32
+        } else {
+            candidate
+        }
+    }
+
+    /// Turn a folded pitch class into a frequency inside the tonic range. When
+    /// the range spans more than one octave, the octave whose Sa (rather than
+    /// Sa') was sung most is preferred.
+    fn place_in_range(&self, pitch_contour: &[PitchFrame], cents: f32) -> f32 {
+        let mut frequency = 440.0 * 2f32.powf(cents.rem_euclid(1200.0) / 1200.0);
+        while frequency / 2.0 >= self.min_frequency {
+            frequency /= 2.0;
+        }
+        while frequency < self.min_frequency {
+            frequency *= 2.0;
+        }
+
+        let mut octaves = Vec::new();
+        while frequency < self.max_frequency || octaves.is_empty() {
+            octaves.push(frequency);
+            frequency *= 2.0;
+        }
+
+        let frames_near = |candidate: f32| {
+            pitch_contour
+                .iter()
+                .filter(|frame| frame.is_voiced())
+                .filter(|frame| (1200.0 * (frame.frequency / candidate).log2()).abs() <= OCTAVE_MATCH_CENTS)
+                .count()
+        };
+        // Reversed so that ties go to the lower octave
+        octaves
+            .iter()
+            .rev()
+            .copied()
+            .max_by_key(|&candidate| frames_near(candidate))
+            .unwrap_or(frequency)
+    }
+}
+
+fn cents_from_a440(frequency: f32) -> f32 {
+    1200.0 * (frequency / 440.0).log2()
+}
+
+fn bin_of(cents: f32) -> usize {
+    ((cents.rem_euclid(1200.0) / BIN_CENTS).round() as usize) % BINS
+}
+
+fn circular_distance(a: usize, b: usize) -> usize {
+    let d = a.abs_diff(b);
+    d.min(BINS - d)
+}
+
+/// Signed distance from `reference` to `cents`, folded into [-600, 600)
+fn folded_offset(cents: f32, reference: f32) -> f32 {
+    (cents - reference + 600.0).rem_euclid(1200.0) - 600.0
+}
+
+fn smooth(histogram: &[f32]) -> Vec<f32> {
+    let reach = (3.0 * SMOOTHING_CENTS / BIN_CENTS).ceil() as usize;
+    let kernel: Vec<f32> = (0..=reach)
+        .map(|steps| (-0.5 * (steps as f32 * BIN_CENTS / SMOOTHING_CENTS).powi(2)).exp())
+        .collect();
+
+    (0..BINS)
+        .map(|target| {
+            (0..BINS)
+                .filter(|&source| circular_distance(source, target) <= reach)
+                .map(|source| histogram[source] * kernel[circular_distance(source, target)])
+                .sum()
+        })
+        .collect()
+}
+
+fn normalise_peak(mut histogram: Vec<f32>) -> Option<Vec<f32>> {
+    let peak = histogram.iter().copied().fold(0.0f32, f32::max);
+    if peak <= 0.0 {
+        return None;
+    }
+    for value in &mut histogram {
+        *value /= peak;
+    }
+    Some(histogram)
+}
+
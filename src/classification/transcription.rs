@@ -0,0 +1,218 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::features::{FeatureStream, PitchFrame};
+use crate::theory::{Note, Swara, TuningSystem};
+
+/// A steady note (nyas) found in the pitch contour
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TranscribedNote {
+    /// Swara and octave nearest to the mean pitch
+    pub note: Note,
+    /// Mean pitch in cents above madhya Sa
+    pub cents: f32,
+    /// Centre time of the note's first pitch frame, in seconds
+    pub onset: f64,
+    /// Centre time of the frame after the note's last, in seconds, so that
+    /// the duration is a whole number of hops
+    pub offset: f64,
+    /// Index of the note's first pitch frame
+    pub start: usize,
+    /// Index one past the note's last pitch frame
+    pub end: usize,
+}
+
+impl TranscribedNote {
+    pub fn duration(&self) -> f64 {
+        self.offset - self.onset
+    }
+
+    /// Cents the note was sung above (positive) or below its swara
+    pub fn deviation(&self, tuning: &TuningSystem) -> f32 {
+        self.cents - self.note.cents(tuning)
+    }
+}
+
+/// Sargam with octave marks; tivra Ma is written `m^` so it can't be misread
+/// as shuddha Ma
+impl fmt::Display for TranscribedNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.note.swara != Swara::TivraMa {
+            return write!(f, "{}", self.note);
+        }
+        let text = self.note.to_string().replace('M', "m^");
+        f.write_str(&text)
+    }
+}
+
+/// Steady notes of a recording, in order of time
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Transcription {
+    pub notes: Vec<TranscribedNote>,
+}
+
+impl Transcription {
+    /// Space-separated sargam, e.g. `S R G m^ P`; parses back with
+    /// `Note::parse_sequence`
+    pub fn sargam(&self) -> String {
+        let notes: Vec<String> = self.notes.iter().map(TranscribedNote::to_string).collect();
+        notes.join(" ")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+}
+
+impl fmt::Display for Transcription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.sargam())
+    }
+}
+
+/// Turns a pitch contour into swaras: finds regions where the pitch holds
+/// steady and quantises each to the nearest swara relative to the tonic
+#[derive(Debug, Clone, Copy)]
+pub struct Transcriber {
+    /// Largest spread of pitch, in cents, within one steady note
+    pub max_range_cents: f32,
+    /// Shortest steady region reported as a note, in seconds; short enough
+    /// to keep the passing notes of a fast phrase
+    pub min_duration: f64,
+    /// Fastest drift of pitch within a note, in cents per second; regions
+    /// moving faster are glides between notes
+    pub max_slope: f32,
+    /// Notes on the same swara separated by a gap (e.g. a breath or a short
+    /// ornament) no longer than this are merged, in seconds
+    pub merge_gap: f64,
+}
+
+impl Default for Transcriber {
+    fn default() -> Self {
+        Self {
+            max_range_cents: 60.0,
+            min_duration: 0.04,
+            max_slope: 300.0,
+            merge_gap: 0.1,
+        }
+    }
+}
+
+/// Steady region being grown: first frame, summed cents (plain and weighted
+/// by frames since the start), and pitch range
+#[derive(Debug, Clone, Copy)]
+struct Region {
+    start: usize,
+    end: usize,
+    total: f32,
+    moment: f32,
+    low: f32,
+    high: f32,
+}
+
+impl Region {
+    fn new(index: usize, cents: f32) -> Self {
+        Self {
+            start: index,
+            end: index + 1,
+            total: cents,
+            moment: 0.0,
+            low: cents,
+            high: cents,
+        }
+    }
+
+    fn push(self, cents: f32) -> Self {
+        Self {
+            end: self.end + 1,
+            total: self.total + cents,
+            moment: self.moment + (self.end - self.start) as f32 * cents,
+            low: self.low.min(cents),
+            high: self.high.max(cents),
+            ..self
+        }
+    }
+
+    fn mean(&self) -> f32 {
+        self.total / (self.end - self.start) as f32
+    }
+
+    /// Least-squares slope of the pitch, in cents per frame
+    fn slope(&self) -> f32 {
+        let frames = (self.end - self.start) as f32;
+        if frames < 2.0 {
+            return 0.0;
+        }
+        let spread = frames * (frames * frames - 1.0) / 12.0;
+        (self.moment - (frames - 1.0) / 2.0 * self.total) / spread
+    }
+}
+
+impl Transcriber {
+    /// Transcribe `contour` against the tonic `sa` (in Hz), with swaras
+    /// pitched by `tuning`
+    pub fn transcribe(&self, contour: &FeatureStream<PitchFrame>, sa: f32, tuning: &TuningSystem) -> Transcription {
+        let frame_rate = contour.info.frame_rate();
+        let min_frames = ((self.min_duration * frame_rate).ceil() as usize).max(1);
+        let max_slope = self.max_slope / frame_rate as f32;
+        let mut notes: Vec<TranscribedNote> = Vec::new();
+
+        let mut emit = |region: Region| {
+            if region.end - region.start < min_frames || region.slope().abs() > max_slope {
+                return;
+            }
+            let Some(note) = nearest_note(region.mean(), tuning) else {
+                return;
+            };
+            let transcribed = TranscribedNote {
+                note,
+                cents: region.mean(),
+                onset: contour.info.time_of(region.start),
+                offset: contour.info.time_of(region.end),
+                start: region.start,
+                end: region.end,
+            };
+            match notes.last_mut() {
+                Some(last) if last.note == note && transcribed.onset - last.offset <= self.merge_gap => {
+                    let (frames, added) = ((last.end - last.start) as f32, (region.end - region.start) as f32);
+                    last.cents = (last.cents * frames + region.total) / (frames + added);
+                    last.offset = transcribed.offset;
+                    last.end = region.end;
+                }
+                _ => notes.push(transcribed),
+            }
+        };
+
+        let mut region: Option<Region> = None;
+        for (index, frame) in contour.frames.iter().enumerate() {
+            let cents = (frame.is_voiced() && sa > 0.0).then(|| 1200.0 * (frame.frequency / sa).log2());
+            region = match (region, cents) {
+                (Some(current), Some(cents))
+                    if cents.max(current.high) - cents.min(current.low) <= self.max_range_cents =>
+                {
+                    Some(current.push(cents))
+                }
+                (current, cents) => {
+                    if let Some(current) = current {
+                        emit(current);
+                    }
+                    cents.map(|cents| Region::new(index, cents))
+                }
+            };
+        }
+        if let Some(current) = region {
+            emit(current);
+        }
+
+        Transcription { notes }
+    }
+}
+
+/// The note nearest to `cents` above madhya Sa, if it lies within the three
+/// octaves
+pub(crate) fn nearest_note(cents: f32, tuning: &TuningSystem) -> Option<Note> {
+    let (swara, deviation) = tuning.nearest(cents);
+    let octave = ((cents - deviation - tuning.cents(swara)) / 1200.0).round() as i32;
+    Note::from_semitones(swara.index() as i32 + 12 * octave)
+}
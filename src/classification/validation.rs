@@ -0,0 +1,276 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use super::ornament::OrnamentPattern;
+use super::raag_db::{Raag, RaagDatabase};
+use crate::theory::tuning::deviation_from_swara;
+use crate::theory::{Note, Octave, Swara};
+
+/// The ten Bhatkhande thaats
+pub const THAATS: [&str; 10] = [
+    "Bilawal", "Kalyan", "Khamaj", "Bhairav", "Bhairavi", "Kafi", "Asavari", "Todi", "Poorvi", "Marwa",
+];
+
+/// Other common spellings of the thaat names
+const THAAT_SPELLINGS: [(&str, &str); 6] = [
+    ("Bilaval", "Bilawal"),
+    ("Asawari", "Asavari"),
+    ("Tori", "Todi"),
+    ("Purvi", "Poorvi"),
+    ("Purbi", "Poorvi"),
+    ("Marva", "Marwa"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum Severity {
+    /// Unusual but usable, e.g. a vakra aroha
+    Warning,
+    /// The definition can't be used as given
+    Error,
+}
+
+/// Where in a raag definition a note appears
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum NoteRole {
+    Aroha,
+    Avaroha,
+    Vadi,
+    Samvadi,
+    /// Note of the characteristic phrase with this index
+    Phrase(usize),
+    /// Swara given its own intonation
+    Intonation,
+    /// Swara of the characteristic ornament with this index
+    Ornament(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum DiagnosticKind {
+    /// Two raags share a name or alias
+    DuplicateName(String),
+    /// `thaat` is not one of the ten thaats
+    UnknownThaat(String),
+    /// The aroha or avaroha has no notes
+    EmptyScale(NoteRole),
+    /// The aroha goes down (or the avaroha up) at this position
+    NonMonotonic { role: NoteRole, index: usize, from: Note, to: Note },
+    /// The vadi, samvadi or a phrase uses a note outside the aroha and avaroha
+    SwaraNotInScale { role: NoteRole, note: Note },
+    /// A phrase with a negative or non-finite weight
+    InvalidPhraseWeight { phrase: usize, weight: f32 },
+    /// A phrase with no notes
+    EmptyPhrase(usize),
+    /// An intonation that isn't a number, or is closer to another swara
+    InvalidIntonation { swara: Swara, cents: f32 },
+    /// A meend or kan that starts and ends on the same swara, or an
+    /// oscillation with a rate limit that isn't a positive number
+    InvalidOrnament(usize),
+}
+
+/// One problem found in a raag definition
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub raag: String,
+    pub severity: Severity,
+    pub kind: DiagnosticKind,
+}
+
+impl Diagnostic {
+    fn new(raag: &Raag, kind: DiagnosticKind) -> Self {
+        let severity = match kind {
+            DiagnosticKind::NonMonotonic { .. } => Severity::Warning,
+            DiagnosticKind::SwaraNotInScale {
+                role: NoteRole::Phrase(_) | NoteRole::Intonation | NoteRole::Ornament(_),
+                ..
+            } => Severity::Warning,
+            _ => Severity::Error,
+        };
+        Self {
+            raag: raag.name.clone(),
+            severity,
+            kind,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for NoteRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NoteRole::Aroha => write!(f, "aroha"),
+            NoteRole::Avaroha => write!(f, "avaroha"),
+            NoteRole::Vadi => write!(f, "vadi"),
+            NoteRole::Samvadi => write!(f, "samvadi"),
+            NoteRole::Phrase(index) => write!(f, "phrase {}", index),
+            NoteRole::Intonation => write!(f, "intonation"),
+            NoteRole::Ornament(index) => write!(f, "ornament {}", index),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: {}: ", severity, self.raag)?;
+
+        match &self.kind {
+            DiagnosticKind::DuplicateName(name) => write!(f, "name {} is used by more than one raag", name),
+            DiagnosticKind::UnknownThaat(thaat) => {
+                write!(f, "unknown thaat {} (expected one of {})", thaat, THAATS.join(", "))
+            }
+            DiagnosticKind::EmptyScale(role) => write!(f, "{} has no notes", role),
+            DiagnosticKind::NonMonotonic { role, index, from, to } => {
+                let direction = if *role == NoteRole::Aroha { "down" } else { "up" };
+                write!(f, "{} goes {} from {} to {} at position {}", role, direction, from, to, index)
+            }
+            DiagnosticKind::SwaraNotInScale { role, note } => {
+                write!(f, "{} note {} is not in the aroha or avaroha", role, note)
+            }
+            DiagnosticKind::InvalidPhraseWeight { phrase, weight } => {
+                write!(f, "phrase {} has invalid weight {}", phrase, weight)
+            }
+            DiagnosticKind::EmptyPhrase(phrase) => write!(f, "phrase {} has no notes", phrase),
+            DiagnosticKind::InvalidIntonation { swara, cents } => {
+                write!(f, "{} at {} cents is closer to another swara", swara, cents)
+            }
+            DiagnosticKind::InvalidOrnament(ornament) => {
+                write!(f, "ornament {} has the same swara at both ends or an invalid rate", ornament)
+            }
+        }
+    }
+}
+
+/// Canonical name of a thaat, accepting any case and common spellings
+pub fn canonical_thaat(name: &str) -> Option<&'static str> {
+    let name = name.trim();
+    THAATS
+        .iter()
+        .copied()
+        .find(|thaat| thaat.eq_ignore_ascii_case(name))
+        .or_else(|| {
+            THAAT_SPELLINGS
+                .iter()
+                .find(|(spelling, _)| spelling.eq_ignore_ascii_case(name))
+                .map(|&(_, thaat)| thaat)
+        })
+}
+
+impl Raag {
+    /// Check the definition for internal consistency
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut report = |kind| diagnostics.push(Diagnostic::new(self, kind));
+
+        if canonical_thaat(&self.thaat).is_none() {
+            report(DiagnosticKind::UnknownThaat(self.thaat.clone()));
+        }
+
+        for (role, scale, ascending) in [(NoteRole::Aroha, &self.aroha, true), (NoteRole::Avaroha, &self.avaroha, false)] {
+            if scale.is_empty() {
+                report(DiagnosticKind::EmptyScale(role));
+            }
+            for (index, pair) in scale.windows(2).enumerate() {
+                let rises = pair[1].semitones() > pair[0].semitones();
+                if rises != ascending {
+                    report(DiagnosticKind::NonMonotonic {
+                        role,
+                        index: index + 1,
+                        from: pair[0],
+                        to: pair[1],
+                    });
+                }
+            }
+        }
+
+        let scale: Vec<Swara> = self.aroha.iter().chain(&self.avaroha).map(|note| note.swara).collect();
+        let in_scale = |note: &Note| scale.contains(&note.swara);
+        for (role, note) in [(NoteRole::Vadi, &self.vadi), (NoteRole::Samvadi, &self.samvadi)] {
+            if !scale.is_empty() && !in_scale(note) {
+                report(DiagnosticKind::SwaraNotInScale {
+                    role,
+                    note: *note,
+                });
+            }
+        }
+
+        for (index, phrase) in self.characteristic_phrases.iter().enumerate() {
+            if phrase.notes.is_empty() {
+                report(DiagnosticKind::EmptyPhrase(index));
+            }
+            if !(phrase.weight.is_finite() && phrase.weight >= 0.0) {
+                report(DiagnosticKind::InvalidPhraseWeight {
+                    phrase: index,
+                    weight: phrase.weight,
+                });
+            }
+            for note in phrase.notes.iter().filter(|note| !scale.is_empty() && !in_scale(note)) {
+                report(DiagnosticKind::SwaraNotInScale {
+                    role: NoteRole::Phrase(index),
+                    note: *note,
+                });
+            }
+        }
+
+        for (&swara, intonation) in &self.intonation {
+            let cents = intonation.cents();
+            if deviation_from_swara(swara, cents).is_none() {
+                report(DiagnosticKind::InvalidIntonation { swara, cents });
+            }
+            if !scale.is_empty() && !scale.contains(&swara) {
+                report(DiagnosticKind::SwaraNotInScale {
+                    role: NoteRole::Intonation,
+                    note: Note::new(swara, Octave::Madhya),
+                });
+            }
+        }
+
+        for (index, ornament) in self.ornaments.iter().enumerate() {
+            let valid = match *ornament {
+                OrnamentPattern::Oscillation { max_rate_hz, .. } => {
+                    max_rate_hz.is_none_or(|rate| rate.is_finite() && rate > 0.0)
+                }
+                OrnamentPattern::Meend { from, to } => from != to,
+                OrnamentPattern::Kan { grace, swara } => grace != swara,
+            };
+            if !valid {
+                report(DiagnosticKind::InvalidOrnament(index));
+            }
+            for swara in ornament.swaras().into_iter().filter(|swara| !scale.is_empty() && !scale.contains(swara)) {
+                report(DiagnosticKind::SwaraNotInScale {
+                    role: NoteRole::Ornament(index),
+                    note: Note::new(swara, Octave::Madhya),
+                });
+            }
+        }
+
+        diagnostics
+    }
+}
+
+impl RaagDatabase {
+    /// Validate every raag and check that names and aliases are unique
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics: Vec<Diagnostic> = self.get_raags().iter().flat_map(Raag::validate).collect();
+
+        let mut seen: Vec<String> = Vec::new();
+        for raag in self.get_raags() {
+            for name in std::iter::once(&raag.name).chain(&raag.aliases) {
+                let key = name.to_lowercase();
+                if seen.contains(&key) {
+                    diagnostics.push(Diagnostic::new(raag, DiagnosticKind::DuplicateName(name.clone())));
+                } else {
+                    seen.push(key);
+                }
+            }
+        }
+
+        diagnostics
+    }
+}
@@ -0,0 +1,264 @@
+use std::io::{Read, Seek};
+use std::path::Path;
+
+use crate::audio::{
+    AudioPreprocessor, AudioReader, DownmixStrategy, ReadOptions, SeparationConfig, ANALYSIS_SAMPLE_RATE,
+};
+use crate::classification::{
+    AudioFeatures, ClassificationResult, DistanceMetric, RaagClassifier, RaagDatabase, TonicEstimate,
+    DEFAULT_DIRECTION_WEIGHT, DEFAULT_ORNAMENT_WEIGHT, DEFAULT_PHRASE_WEIGHT,
+};
+use crate::error::{RaagError, Result};
+use crate::features::{
+    ChromagramExtractor, FeatureStream, LongTermSpectrum, PitchExtractor, SpectralAnalyzer, StreamInfo,
+};
+use crate::theory::TuningSystem;
+
+/// FFT size of the long-term spectrum used to find drone peaks; long enough to
+/// resolve Sa and Pa of a low tanpura
+const DRONE_FFT_SIZE: usize = 8192;
+
+/// Settings for the full reader → features → classifier pipeline
+#[derive(Debug, Clone, Copy)]
+pub struct DetectorConfig {
+    /// Which part of the recording to decode
+    pub read_options: ReadOptions,
+    /// How multi-channel recordings are reduced to mono
+    pub downmix: DownmixStrategy,
+    /// Sample rate features are extracted at (`None` = the recording's native rate)
+    pub analysis_rate: Option<u32>,
+    /// FFT size used by the chromagram and spectral extractors
+    pub fft_size: usize,
+    /// Distance used to compare pitch-class distributions with raag templates
+    pub metric: DistanceMetric,
+    /// Bins per octave of the pitch-class distribution
+    pub pcd_resolution: usize,
+    /// Largest distance of the best raag; beyond it the detector reports no
+    /// raag (`None` = the metric's `default_max_distance`)
+    pub max_distance: Option<f32>,
+    /// Share of the aroha/avaroha match in each raag's distance (0 to 1)
+    pub direction_weight: f32,
+    /// How strongly hearing a raag's characteristic phrases raises its share
+    pub phrase_weight: f32,
+    /// How strongly hearing a raag's characteristic ornaments raises its share
+    pub ornament_weight: f32,
+    /// Known Sa in Hz; skips tonic estimation when set
+    pub tonic: Option<f32>,
+    /// Tuning of raag notes; with a known tonic the chromagram uses it too
+    pub tuning: TuningSystem,
+    /// Separate the lead melody from percussion and drone before pitch
+    /// tracking (`None` = analyse the mix as it is)
+    pub separation: Option<SeparationConfig>,
+}
+
+impl Default for DetectorConfig {
+    fn default() -> Self {
+        Self {
+            read_options: ReadOptions::default(),
+            downmix: DownmixStrategy::default(),
+            analysis_rate: Some(ANALYSIS_SAMPLE_RATE),
+            fft_size: 2048,
+            metric: DistanceMetric::default(),
+            pcd_resolution: 12,
+            max_distance: None,
+            direction_weight: DEFAULT_DIRECTION_WEIGHT,
+            phrase_weight: DEFAULT_PHRASE_WEIGHT,
+            ornament_weight: DEFAULT_ORNAMENT_WEIGHT,
+            tonic: None,
+            tuning: TuningSystem::default(),
+            separation: None,
+        }
+    }
+}
+
+/// Outcome of running the pipeline over one recording
+pub struct Detection {
+    /// Ranked raag candidates, estimated tonic and supporting evidence
+    pub classification: ClassificationResult,
+    /// Sample rate the features were extracted at
+    pub analysis_rate: u32,
+    pub features: AudioFeatures,
+    /// Lead melody the pitch was tracked on, at the analysis rate, when
+    /// separation is enabled
+    pub lead: Option<Vec<f32>>,
+}
+
+/// One-stop entry point: decodes audio, extracts features and classifies the raag
+pub struct RaagDetector {
+    config: DetectorConfig,
+    classifier: RaagClassifier,
+}
+
+impl RaagDetector {
+    pub fn new(config: DetectorConfig) -> Self {
+        Self {
+            config,
+            classifier: RaagClassifier::new()
+                .with_metric(config.metric)
+                .with_resolution(config.pcd_resolution)
+                .with_max_distance(config.max_distance)
+                .with_direction_weight(config.direction_weight)
+                .with_phrase_weight(config.phrase_weight)
+                .with_ornament_weight(config.ornament_weight)
+                .with_tonic(config.tonic)
+                .with_tuning(config.tuning),
+        }
+    }
+
+    /// Classify against `database` instead of the bundled catalogue
+    pub fn with_database(mut self, database: RaagDatabase) -> Self {
+        self.classifier = self.classifier.with_database(database);
+        self
+    }
+
+    pub fn config(&self) -> &DetectorConfig {
+        &self.config
+    }
+
+    /// Raags the detector classifies against
+    pub fn database(&self) -> &RaagDatabase {
+        self.classifier.database()
+    }
+
+    /// Decode a file using the configured read options
+    pub fn load<P: AsRef<Path>>(&self, path: P) -> Result<AudioReader> {
+        AudioReader::from_file_with_options(path, &self.config.read_options)
+    }
+
+    pub fn detect_file<P: AsRef<Path>>(&self, path: P) -> Result<Detection> {
+        let audio = self.load(path)?;
+        self.detect(&audio)
+    }
+
+    pub fn detect_reader<R>(&self, reader: R) -> Result<Detection>
+    where
+        R: Read + Seek + Send + Sync + 'static,
+    {
+        let audio = AudioReader::from_reader_with_options(reader, &self.config.read_options)?;
+        self.detect(&audio)
+    }
+
+    /// Run feature extraction and classification on already decoded audio.
+    /// Times in the result count from the start of the recording, so they
+    /// include the offset the audio was read from.
+    pub fn detect(&self, audio: &AudioReader) -> Result<Detection> {
+        let mono = audio.to_mono(self.config.downmix);
+        self.analyse(&mono, audio.sample_rate, audio.start_time)
+    }
+
+    /// Run feature extraction and classification on a mono signal, with
+    /// times counted from its first sample
+    pub fn detect_mono(&self, samples: &[f32], sample_rate: u32) -> Result<Detection> {
+        self.analyse(samples, sample_rate, 0.0)
+    }
+
+    fn analyse(&self, samples: &[f32], sample_rate: u32, start_time: f64) -> Result<Detection> {
+        // Bring every recording to the same rate so features are comparable
+        let analysis_rate = self.config.analysis_rate.unwrap_or(sample_rate);
+        if analysis_rate == 0 {
+            return Err(RaagError::InvalidSampleRate(analysis_rate));
+        }
+        let samples = AudioPreprocessor::resample(samples, sample_rate, analysis_rate);
+
+        // Only needed to find the drone when the tonic has to be estimated
+        let spectrum = self
+            .config
+            .tonic
+            .is_none()
+            .then(|| SpectralAnalyzer::new(analysis_rate, DRONE_FFT_SIZE).long_term_spectrum(&samples));
+        let (lead, tonic) = match &self.config.separation {
+            Some(separation) => {
+                let (lead, tonic) = self.separate(&samples, analysis_rate, separation, spectrum.as_ref())?;
+                (Some(lead), Some(tonic))
+            }
+            None => (None, None),
+        };
+        let features =
+            self.extract_features(lead.as_deref().unwrap_or(&samples), &samples, analysis_rate, start_time, spectrum);
+        // Separation has already found the tonic, so it isn't estimated again
+        let classification = match tonic {
+            Some(tonic) => self.classifier.classify_with_tonic(&features, tonic)?,
+            None => self.classifier.classify(&features)?,
+        };
+
+        Ok(Detection {
+            classification,
+            analysis_rate,
+            features,
+            lead,
+        })
+    }
+
+    /// Lead melody of a mix and its tonic: the configured one or, failing
+    /// that, one estimated from the mix's harmonic part and `spectrum`. The
+    /// drone is keyed to that tonic and taken off the same harmonic part, so
+    /// the median filters run over the recording once.
+    fn separate(
+        &self,
+        samples: &[f32],
+        sample_rate: u32,
+        separation: &SeparationConfig,
+        spectrum: Option<&LongTermSpectrum>,
+    ) -> Result<(Vec<f32>, TonicEstimate)> {
+        let harmonic = AudioPreprocessor::harmonic(samples, separation);
+        // A configured tonic needs no pitch contour
+        let contour = match self.config.tonic {
+            Some(_) => Vec::new(),
+            None => PitchExtractor::new(sample_rate).extract_f0(&harmonic),
+        };
+        let tonic = self.classifier.estimate_tonic(&contour, spectrum)?;
+        let lead = AudioPreprocessor::remove_drone(&harmonic, sample_rate, tonic.frequency, separation);
+        Ok((lead, tonic))
+    }
+
+    /// Pitch and chroma come from `lead`, while the spectral streams, which
+    /// need the drone to find the tonic, come from the full `mix` (as does
+    /// `long_term_spectrum`). Frame times are offset by `start_time`, the
+    /// position of the first sample in the recording.
+    fn extract_features(
+        &self,
+        lead: &[f32],
+        mix: &[f32],
+        sample_rate: u32,
+        start_time: f64,
+        long_term_spectrum: Option<LongTermSpectrum>,
+    ) -> AudioFeatures {
+        let pitch_extractor = PitchExtractor::new(sample_rate);
+        let mut chroma_extractor = ChromagramExtractor::new(sample_rate, self.config.fft_size);
+        if let Some(sa) = self.config.tonic {
+            chroma_extractor = chroma_extractor.with_tuning(sa, self.config.tuning);
+        }
+        let spectral_analyzer = SpectralAnalyzer::new(sample_rate, self.config.fft_size);
+
+        let info = |frame_config| StreamInfo::new(sample_rate, frame_config, start_time);
+
+        AudioFeatures {
+            pitch_contour: FeatureStream::new(
+                info(*pitch_extractor.frame_config()),
+                pitch_extractor.extract_f0(lead),
+            ),
+            chromagram: FeatureStream::new(
+                info(*chroma_extractor.frame_config()),
+                chroma_extractor.extract_chromagram(lead),
+            ),
+            spectral_centroid: FeatureStream::new(
+                info(*spectral_analyzer.frame_config()),
+                spectral_analyzer.spectral_centroids(mix),
+            ),
+            long_term_spectrum,
+        }
+    }
+}
+
+impl Detection {
+    /// Name of the detected raag, or `None` if the classifier abstained
+    pub fn raag(&self) -> Option<&str> {
+        self.classification.raag()
+    }
+}
+
+impl Default for RaagDetector {
+    fn default() -> Self {
+        Self::new(DetectorConfig::default())
+    }
+}
@@ -0,0 +1,94 @@
+use hound::Error as HoundError;
+use symphonia::core::errors::Error as SymphoniaError;
+use thiserror::Error;
+
+use crate::classification::Diagnostic;
+
+pub type Result<T> = std::result::Result<T, RaagError>;
+
+/// Every failure the audio, feature and classification layers can report.
+///
+/// Variants are grouped by failure class so that callers can tell bad input
+/// (unsupported or corrupt audio) apart from recordings that simply don't
+/// contain enough usable material, and from lookups of unknown raags.
+#[derive(Debug, Error)]
+pub enum RaagError {
+    // Audio input
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Unrecognized audio format (the stream matched no known container or codec)")]
+    UnrecognizedFormat,
+    #[error("Unsupported codec: {0}")]
+    UnsupportedCodec(String),
+    #[error("Unsupported WAV sample format: {bits}-bit {format}")]
+    UnsupportedSampleFormat { bits: u16, format: String },
+    #[error("No supported audio tracks found")]
+    NoSupportedTrack,
+    #[error("Malformed audio data: {0}")]
+    MalformedAudio(String),
+    #[error("Decoder needed a reset it could not recover from")]
+    DecoderReset,
+    #[error("Seek failed: {0}")]
+    SeekFailed(String),
+    #[error("Decoding limit exceeded: {0}")]
+    LimitExceeded(String),
+    #[error("Giving up after {0} consecutive corrupt packets")]
+    CorruptStream(usize),
+    #[error("Stream changed from {sample_rate} Hz/{channels} channels to {new_sample_rate} Hz/{new_channels} channels mid-file")]
+    StreamChanged {
+        sample_rate: u32,
+        channels: u16,
+        new_sample_rate: u32,
+        new_channels: u16,
+    },
+    #[error("No audio data found in file")]
+    NoAudioData,
+    #[error("Invalid sample rate: {0} Hz")]
+    InvalidSampleRate(u32),
+
+    // Feature extraction
+    #[error("Audio too short to extract any analysis frames")]
+    AudioTooShort,
+    #[error("No voiced frames found in the pitch contour")]
+    NoVoicedFrames,
+
+    // Classification
+    #[error("Unknown raag: {0}")]
+    UnknownRaag(String),
+    #[error("Raag database is empty")]
+    EmptyDatabase,
+    #[error("Invalid raag definitions: {0}")]
+    InvalidRaagDefinitions(String),
+    #[error("Raag definitions failed validation:{}", list_diagnostics(.0))]
+    RaagValidation(Vec<Diagnostic>),
+}
+
+fn list_diagnostics(diagnostics: &[Diagnostic]) -> String {
+    diagnostics.iter().map(|diagnostic| format!("\n  {}", diagnostic)).collect()
+}
+
+impl From<SymphoniaError> for RaagError {
+    fn from(err: SymphoniaError) -> Self {
+        match err {
+            SymphoniaError::IoError(err) => RaagError::Io(err),
+            SymphoniaError::Unsupported(what) => RaagError::UnsupportedCodec(what.to_string()),
+            SymphoniaError::DecodeError(what) => RaagError::MalformedAudio(what.to_string()),
+            SymphoniaError::ResetRequired => RaagError::DecoderReset,
+            SymphoniaError::SeekError(kind) => RaagError::SeekFailed(format!("{:?}", kind)),
+            SymphoniaError::LimitError(what) => RaagError::LimitExceeded(what.to_string()),
+        }
+    }
+}
+
+impl From<HoundError> for RaagError {
+    fn from(err: HoundError) -> Self {
+        match err {
+            HoundError::IoError(err) => RaagError::Io(err),
+            HoundError::Unsupported => RaagError::UnsupportedCodec("WAV encoding".to_string()),
+            HoundError::FormatError(what) => RaagError::MalformedAudio(what.to_string()),
+            err @ (HoundError::TooWide | HoundError::UnfinishedSample | HoundError::InvalidSampleFormat) => {
+                RaagError::MalformedAudio(err.to_string())
+            }
+        }
+    }
+}
@@ -1,9 +1,20 @@
 use rustfft::{FftPlanner, num_complex::Complex};
 use std::f32::consts::PI;
 
+use super::FrameConfig;
+use crate::theory::TuningSystem;
+
+/// Frequency of middle C (A4 = 440 Hz), bin 0 of the default chromagram
+const MIDDLE_C_HZ: f32 = 261.6256;
+
+/// Twelve-bin chroma features. By default bin 0 is C in equal temperament;
+/// with a known tonic, bin 0 is Sa and bins follow a tuning system's swaras.
 pub struct ChromagramExtractor {
     sample_rate: u32,
     fft_size: usize,
+    frame_config: FrameConfig,
+    reference: f32,
+    tuning: TuningSystem,
 }
 
 impl ChromagramExtractor {
@@ -11,18 +22,40 @@ impl ChromagramExtractor {
         Self {
             sample_rate,
             fft_size,
+            frame_config: FrameConfig::new(fft_size, fft_size / 4),
+            reference: MIDDLE_C_HZ,
+            tuning: TuningSystem::EqualTemperament,
         }
     }
 
+    /// Make bin 0 the tonic `sa` (in Hz) and map frequencies to the nearest
+    /// swara of `tuning`
+    pub fn with_tuning(mut self, sa: f32, tuning: TuningSystem) -> Self {
+        self.reference = sa;
+        self.tuning = tuning;
+        self
+    }
+
+    /// Override the hop and padding; the window length always equals the FFT size
+    pub fn with_frame_config(mut self, frame_config: FrameConfig) -> Self {
+        self.frame_config = FrameConfig {
+            window_size: self.fft_size,
+            ..frame_config
+        };
+        self
+    }
+
+    pub fn frame_config(&self) -> &FrameConfig {
+        &self.frame_config
+    }
+
     pub fn extract_chromagram(&self, samples: &[f32]) -> Vec<[f32; 12]> {
-        let hop_size = self.fft_size / 4;
         let mut planner = FftPlanner::new();
         let fft = planner.plan_fft_forward(self.fft_size);
 
-        samples
-            .chunks(self.fft_size)
-            .step_by(hop_size)
-            .map(|window| self.compute_chroma_vector(window, &fft))
+        self.frame_config
+            .frames(samples)
+            .map(|window| self.compute_chroma_vector(&window, &fft))
             .collect()
     }
 
@@ -71,8 +104,7 @@ impl ChromagramExtractor {
     }
 
     fn frequency_to_chroma(&self, frequency: f32) -> usize {
-        // Convert frequency to MIDI note number, then to chroma class
-        let midi_note = 69.0 + 12.0 * (frequency / 440.0).log2();
-        (midi_note.round() as usize) % 12
+        let cents = 1200.0 * (frequency / self.reference).log2();
+        self.tuning.nearest(cents).0.index()
     }
 }
\ No newline at end of file
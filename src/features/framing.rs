@@ -0,0 +1,137 @@
+use std::borrow::Cow;
+
+/// How the signal is padded so that every frame has exactly `window_size` samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FramePadding {
+    /// Frame `i` is centred on sample `i * hop_size`; half a window of zeros is
+    /// added on both sides
+    #[default]
+    Center,
+    /// Frame `i` starts at sample `i * hop_size`; the last partial frame is
+    /// zero-padded at the end
+    End,
+    /// Only frames that fit entirely inside the signal are produced
+    None,
+}
+
+/// Window/hop layout shared by all feature extractors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameConfig {
+    pub window_size: usize,
+    pub hop_size: usize,
+    pub padding: FramePadding,
+}
+
+impl FrameConfig {
+    pub fn new(window_size: usize, hop_size: usize) -> Self {
+        Self {
+            window_size,
+            hop_size: hop_size.max(1),
+            padding: FramePadding::default(),
+        }
+    }
+
+    pub fn with_padding(mut self, padding: FramePadding) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Number of frames produced for a signal of `len` samples
+    pub fn frame_count(&self, len: usize) -> usize {
+        if len == 0 {
+            return 0;
+        }
+
+        match self.padding {
+            FramePadding::Center | FramePadding::End => (len - 1) / self.hop_size + 1,
+            FramePadding::None if len < self.window_size => 0,
+            FramePadding::None => (len - self.window_size) / self.hop_size + 1,
+        }
+    }
+
+    /// Index of the first sample of frame `index`, relative to the unpadded signal.
+    /// Negative for centred frames that start in the leading padding.
+    pub fn frame_start(&self, index: usize) -> isize {
+        let start = (index * self.hop_size) as isize;
+        match self.padding {
+            FramePadding::Center => start - (self.window_size / 2) as isize,
+            FramePadding::End | FramePadding::None => start,
+        }
+    }
+
+    /// Time in seconds of the centre of frame `index`
+    pub fn frame_time(&self, index: usize, sample_rate: u32) -> f64 {
+        let centre = self.frame_start(index) as f64 + self.window_size as f64 / 2.0;
+        centre / sample_rate as f64
+    }
+
+    /// Centre times of the first `count` frames
+    pub fn frame_times(&self, count: usize, sample_rate: u32) -> Vec<f64> {
+        (0..count).map(|index| self.frame_time(index, sample_rate)).collect()
+    }
+
+    /// Iterate over the frames of `samples`, each exactly `window_size` long
+    pub fn frames<'a>(&self, samples: &'a [f32]) -> Frames<'a> {
+        Frames {
+            config: *self,
+            samples,
+            index: 0,
+            count: self.frame_count(samples.len()),
+        }
+    }
+}
+
+/// Iterator over the frames of a signal.
+///
+/// Frames lying entirely inside the signal are borrowed; frames that overlap
+/// the padding are copied and zero-filled.
+pub struct Frames<'a> {
+    config: FrameConfig,
+    samples: &'a [f32],
+    index: usize,
+    count: usize,
+}
+
+impl<'a> Iterator for Frames<'a> {
+    type Item = Cow<'a, [f32]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.count {
+            return None;
+        }
+
+        let window_size = self.config.window_size;
+        let start = self.config.frame_start(self.index);
+        self.index += 1;
+
+        if start >= 0 && start as usize + window_size <= self.samples.len() {
+            let start = start as usize;
+            return Some(Cow::Borrowed(&self.samples[start..start + window_size]));
+        }
+
+        let frame = (0..window_size)
+            .map(|offset| {
+                let position = start + offset as isize;
+                if position >= 0 {
+                    self.samples.get(position as usize).copied().unwrap_or(0.0)
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        Some(Cow::Owned(frame))
+    }
+
+    /// Skips frames without building them
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.index = self.index.saturating_add(n).min(self.count);
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.count - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Frames<'_> {}
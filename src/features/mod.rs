@@ -1,7 +1,11 @@
+pub mod framing;
 pub mod pitch;
 pub mod chromagram;
 pub mod spectral;
+pub mod timeline;
 
-pub use pitch::PitchExtractor;
+pub use framing::{FrameConfig, FramePadding, Frames};
+pub use pitch::{PitchAlgorithm, PitchExtractor, PitchFrame};
 pub use chromagram::ChromagramExtractor;
-pub use spectral::SpectralAnalyzer;
\ No newline at end of file
+pub use spectral::{LongTermSpectrum, SpectralAnalyzer};
+pub use timeline::{format_timestamp, FeatureStream, StreamInfo};
\ No newline at end of file
@@ -1,55 +1,419 @@
 use rustfft::{FftPlanner, num_complex::Complex};
 
+use super::FrameConfig;
+
+/// Lowest fundamental frequency searched for (Hz)
+const MIN_FREQUENCY: f32 = 80.0;
+/// Highest fundamental frequency searched for (Hz)
+const MAX_FREQUENCY: f32 = 800.0;
+
+/// Absolute CMNDF threshold used by plain YIN
+const YIN_THRESHOLD: f32 = 0.1;
+
+/// pYIN: number of thresholds the Beta prior is spread over
+const PYIN_THRESHOLDS: usize = 100;
+/// pYIN: shape parameters of the Beta(2, 18) threshold prior (mean 0.1)
+const PYIN_BETA_B: i32 = 18;
+/// pYIN: share of a threshold's mass given to the global minimum when no trough is below it
+const PYIN_NO_TROUGH_PROBABILITY: f32 = 0.01;
+/// pYIN: width of a pitch state in cents
+const PYIN_BIN_CENTS: f32 = 20.0;
+/// pYIN: fastest pitch movement the HMM allows, in semitones per second
+const PYIN_MAX_SEMITONES_PER_SECOND: f32 = 430.0;
+/// pYIN: probability of staying voiced (or unvoiced) from one frame to the next
+const PYIN_VOICING_STAY_PROBABILITY: f32 = 0.99;
+
+/// Pitch estimate for one analysis frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PitchFrame {
+    /// Fundamental frequency in Hz, or 0.0 if the frame is unvoiced
+    pub frequency: f32,
+    /// Probability that the frame contains a pitched sound
+    pub voicing: f32,
+}
+
+impl PitchFrame {
+    pub const UNVOICED: PitchFrame = PitchFrame { frequency: 0.0, voicing: 0.0 };
+
+    pub fn is_voiced(&self) -> bool {
+        self.frequency > 0.0
+    }
+}
+
+/// Pitch tracking algorithm used by `PitchExtractor`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PitchAlgorithm {
+    /// YIN with a fixed threshold, decided frame by frame
+    Yin,
+    /// Probabilistic YIN with Viterbi smoothing across frames
+    #[default]
+    PYin,
+}
+
 pub struct PitchExtractor {
     sample_rate: u32,
+    algorithm: PitchAlgorithm,
+    frame_config: FrameConfig,
+}
+
+/// A local minimum of the CMNDF, refined to sub-sample precision
+#[derive(Debug, Clone, Copy)]
+struct Trough {
+    period: f32,
+    value: f32,
 }
 
 impl PitchExtractor {
     pub fn new(sample_rate: u32) -> Self {
-        Self { sample_rate }
+        Self::with_algorithm(sample_rate, PitchAlgorithm::default())
+    }
+
+    /// Frames are 2048 samples with a 512-sample hop, or longer at sample
+    /// rates where 2048 samples can't hold two of the longest periods searched
+    /// for (above 48 kHz)
+    pub fn with_algorithm(sample_rate: u32, algorithm: PitchAlgorithm) -> Self {
+        let mut extractor = Self {
+            sample_rate,
+            algorithm,
+            frame_config: FrameConfig::new(2048, 512),
+        };
+        let window_size = (2 * (extractor.max_period() + 1)).next_power_of_two().max(2048);
+        extractor.frame_config = FrameConfig::new(window_size, window_size / 4);
+        extractor
+    }
+
+    pub fn with_frame_config(mut self, frame_config: FrameConfig) -> Self {
+        self.frame_config = frame_config;
+        self
+    }
+
+    pub fn frame_config(&self) -> &FrameConfig {
+        &self.frame_config
     }
 
-    pub fn extract_f0(&self, samples: &[f32]) -> Vec<f32> {
-        // Placeholder for F0 extraction using autocorrelation
-        // This is a simplified version - real implementation would be more sophisticated
-        let window_size = 2048;
-        let hop_size = 512;
+    pub fn extract_f0(&self, samples: &[f32]) -> Vec<PitchFrame> {
+        let mut planner = FftPlanner::new();
+        let frames = self
+            .frame_config
+            .frames(samples)
+            .map(|window| self.cumulative_mean_normalized_difference(&window, &mut planner));
 
-        samples
-            .chunks(window_size)
-            .step_by(hop_size)
-            .map(|window| self.autocorrelation_pitch(window))
+        match self.algorithm {
+            PitchAlgorithm::Yin => frames.map(|cmndf| self.yin_pitch(&cmndf)).collect(),
+            PitchAlgorithm::PYin => {
+                let candidates: Vec<Vec<Trough>> = frames.map(|cmndf| self.pyin_candidates(&cmndf)).collect();
+                self.viterbi_decode(&candidates, self.frame_config.hop_size)
+            }
+        }
+    }
+
+    fn min_period(&self) -> usize {
+        ((self.sample_rate as f32 / MAX_FREQUENCY).floor() as usize).max(2)
+    }
+
+    fn max_period(&self) -> usize {
+        (self.sample_rate as f32 / MIN_FREQUENCY).ceil() as usize
+    }
+
+    /// YIN's cumulative mean normalized difference function, indexed by lag.
+    ///
+    /// Lags stop at half the window when it is too short to hold two of the
+    /// longest periods, so only the lowest pitches are lost. Returns an empty
+    /// vector for frames too short to hold even the shortest period, or too
+    /// quiet to carry a pitch.
+    fn cumulative_mean_normalized_difference(&self, window: &[f32], planner: &mut FftPlanner<f32>) -> Vec<f32> {
+        let max_lag = (self.max_period() + 1).min(window.len() / 2);
+        if max_lag < self.min_period() + 2 {
+            return Vec::new();
+        }
+        let integration = window.len() - max_lag;
+
+        // Cross-correlate the first `integration` samples against the whole window
+        let fft_size = (window.len() + integration).next_power_of_two();
+        let fft = planner.plan_fft_forward(fft_size);
+        let ifft = planner.plan_fft_inverse(fft_size);
+
+        let mut head: Vec<Complex<f32>> = window[..integration]
+            .iter()
+            .map(|&x| Complex::new(x, 0.0))
+            .chain(std::iter::repeat(Complex::new(0.0, 0.0)))
+            .take(fft_size)
+            .collect();
+        let mut full: Vec<Complex<f32>> = window
+            .iter()
+            .map(|&x| Complex::new(x, 0.0))
+            .chain(std::iter::repeat(Complex::new(0.0, 0.0)))
+            .take(fft_size)
+            .collect();
+        fft.process(&mut head);
+        fft.process(&mut full);
+
+        let mut correlation: Vec<Complex<f32>> = head
+            .iter()
+            .zip(&full)
+            .map(|(h, f)| h.conj() * f)
+            .collect();
+        ifft.process(&mut correlation);
+        let scale = 1.0 / fft_size as f32;
+
+        // d(tau) = sum x[j]^2 + sum x[j + tau]^2 - 2 r(tau), over j < integration
+        let mut energy_prefix = Vec::with_capacity(window.len() + 1);
+        energy_prefix.push(0.0f64);
+        for &x in window {
+            energy_prefix.push(energy_prefix.last().unwrap() + (x as f64) * (x as f64));
+        }
+        let head_energy = energy_prefix[integration];
+        if head_energy < 1e-10 {
+            return Vec::new();
+        }
+
+        let mut cmndf = vec![1.0f32; max_lag];
+        let mut running_sum = 0.0f64;
+        for (tau, value) in cmndf.iter_mut().enumerate().skip(1) {
+            let shifted_energy = energy_prefix[tau + integration] - energy_prefix[tau];
+            let difference = (head_energy + shifted_energy - 2.0 * (correlation[tau].re * scale) as f64).max(0.0);
+            running_sum += difference;
+            *value = if running_sum > 0.0 {
+                (difference * tau as f64 / running_sum) as f32
+            } else {
+                1.0
+            };
+        }
+
+        cmndf
+    }
+
+    /// Local minima of the CMNDF within the searched period range
+    fn troughs(&self, cmndf: &[f32]) -> Vec<Trough> {
+        let min_period = self.min_period();
+        let max_period = self.max_period().min(cmndf.len().saturating_sub(2));
+
+        (min_period..=max_period)
+            .filter(|&tau| cmndf[tau] < cmndf[tau - 1] && cmndf[tau] <= cmndf[tau + 1])
+            .map(|tau| Self::refine_trough(cmndf, tau))
             .collect()
     }
 
-    fn autocorrelation_pitch(&self, window: &[f32]) -> f32 {
-        // Simplified autocorrelation for pitch detection
-        // Real implementation would use YIN algorithm or similar
-        let min_period = self.sample_rate as usize / 800; // ~800 Hz max
-        let max_period = self.sample_rate as usize / 80;  // ~80 Hz min
+    /// Parabolic interpolation around an integer trough
+    fn refine_trough(cmndf: &[f32], tau: usize) -> Trough {
+        let (left, centre, right) = (cmndf[tau - 1], cmndf[tau], cmndf[tau + 1]);
+        let curvature = left - 2.0 * centre + right;
+        if curvature.abs() < f32::EPSILON {
+            return Trough { period: tau as f32, value: centre };
+        }
+
+        let shift = (0.5 * (left - right) / curvature).clamp(-0.5, 0.5);
+        Trough {
+            period: tau as f32 + shift,
+            value: (centre - 0.25 * (left - right) * shift).max(0.0),
+        }
+    }
+
+    fn yin_pitch(&self, cmndf: &[f32]) -> PitchFrame {
+        let troughs = self.troughs(cmndf);
+
+        let Some(global_min) = troughs.iter().copied().min_by(|a, b| a.value.total_cmp(&b.value)) else {
+            return PitchFrame::UNVOICED;
+        };
 
-        let mut max_correlation = 0.0;
-        let mut best_period = min_period;
+        // The first dip under the threshold avoids picking a multiple of the period
+        match troughs.iter().find(|t| t.value < YIN_THRESHOLD) {
+            Some(trough) => PitchFrame {
+                frequency: self.sample_rate as f32 / trough.period,
+                voicing: (1.0 - trough.value).clamp(0.0, 1.0),
+            },
+            None => PitchFrame {
+                frequency: 0.0,
+                voicing: (1.0 - global_min.value).clamp(0.0, 1.0),
+            },
+        }
+    }
 
-        for period in min_period..max_period.min(window.len() / 2) {
-            let correlation = self.autocorrelation_at_lag(window, period);
-            if correlation > max_correlation {
-                max_correlation = correlation;
-                best_period = period;
+    /// Candidate periods for one frame, with `value` replaced by the probability
+    /// mass the Beta threshold prior assigns to each
+    fn pyin_candidates(&self, cmndf: &[f32]) -> Vec<Trough> {
+        let troughs = self.troughs(cmndf);
+        let Some(global_min) = troughs
+            .iter()
+            .enumerate()
+            .min_by(|a, b| a.1.value.total_cmp(&b.1.value))
+            .map(|(index, _)| index)
+        else {
+            return Vec::new();
+        };
+
+        let mut probabilities = vec![0.0f32; troughs.len()];
+        let mut previous_cdf = 0.0;
+        for step in 1..=PYIN_THRESHOLDS {
+            let threshold = step as f32 / PYIN_THRESHOLDS as f32;
+            let cdf = beta_2_b_cdf(threshold, PYIN_BETA_B);
+            let mass = cdf - previous_cdf;
+            previous_cdf = cdf;
+
+            match troughs.iter().position(|t| t.value < threshold) {
+                Some(index) => probabilities[index] += mass,
+                None => probabilities[global_min] += mass * PYIN_NO_TROUGH_PROBABILITY,
             }
         }
 
-        self.sample_rate as f32 / best_period as f32
+        troughs
+            .into_iter()
+            .zip(probabilities)
+            .filter(|(_, probability)| *probability > 0.0)
+            .map(|(trough, probability)| Trough { period: trough.period, value: probability })
+            .collect()
     }
 
-    fn autocorrelation_at_lag(&self, signal: &[f32], lag: usize) -> f32 {
-        let mut correlation = 0.0;
-        let n = signal.len() - lag;
+    /// Choose the most likely pitch/voicing path through the pYIN candidates.
+    ///
+    /// Every pitch bin has a voiced and an unvoiced state; unvoiced states keep
+    /// their bin so the pitch can resume smoothly after a pause.
+    fn viterbi_decode(&self, candidates: &[Vec<Trough>], hop_size: usize) -> Vec<PitchFrame> {
+        if candidates.is_empty() {
+            return Vec::new();
+        }
+
+        let bins = ((1200.0 * (MAX_FREQUENCY / MIN_FREQUENCY).log2()) / PYIN_BIN_CENTS).ceil() as usize + 1;
+        let hop_seconds = hop_size as f32 / self.sample_rate as f32;
+        let band = ((PYIN_MAX_SEMITONES_PER_SECOND * hop_seconds * 100.0 / PYIN_BIN_CENTS).round() as usize).clamp(1, 63);
+
+        let bin_of = |frequency: f32| -> usize {
+            ((1200.0 * (frequency / MIN_FREQUENCY).log2() / PYIN_BIN_CENTS).round().max(0.0) as usize).min(bins - 1)
+        };
+        let frequency_of = |bin: usize| MIN_FREQUENCY * 2f32.powf(bin as f32 * PYIN_BIN_CENTS / 1200.0);
+
+        // Triangular pitch transition weights, normalised per source bin
+        let log_transition_norm: Vec<f32> = (0..bins)
+            .map(|source| {
+                let low = source.saturating_sub(band);
+                let high = (source + band).min(bins - 1);
+                (low..=high)
+                    .map(|target| (band + 1 - source.abs_diff(target)) as f32)
+                    .sum::<f32>()
+                    .ln()
+            })
+            .collect();
+        let log_transition = |source: usize, target: usize| {
+            ((band + 1 - source.abs_diff(target)) as f32).ln() - log_transition_norm[source]
+        };
+        let log_stay = PYIN_VOICING_STAY_PROBABILITY.ln();
+        let log_switch = (1.0 - PYIN_VOICING_STAY_PROBABILITY).ln();
+
+        // Observation log-likelihoods: voiced states in [0, bins), unvoiced in [bins, 2 * bins)
+        let observe = |frame: &[Trough], out: &mut Vec<f32>| {
+            out.clear();
+            out.resize(2 * bins, 0.0);
+            let mut voiced_total = 0.0;
+            for candidate in frame {
+                let bin = bin_of(self.sample_rate as f32 / candidate.period);
+                out[bin] += candidate.value;
+                voiced_total += candidate.value;
+            }
+            let unvoiced = (1.0 - voiced_total).max(0.0) / bins as f32;
+            for value in &mut out[bins..] {
+                *value = unvoiced;
+            }
+            for value in out.iter_mut() {
+                *value = value.max(1e-30).ln();
+            }
+        };
+
+        let mut observation = Vec::with_capacity(2 * bins);
+        observe(&candidates[0], &mut observation);
+        let mut scores: Vec<f32> = observation.iter().map(|o| o - ((2 * bins) as f32).ln()).collect();
+        let mut next_scores = vec![0.0f32; 2 * bins];
+
+        // Backpointer per state: low 7 bits = source bin offset + band, high bit = source unvoiced
+        let mut backpointers = vec![0u8; candidates.len() * 2 * bins];
+
+        for (t, frame) in candidates.iter().enumerate().skip(1) {
+            observe(frame, &mut observation);
+            let pointers = &mut backpointers[t * 2 * bins..(t + 1) * 2 * bins];
 
-        for i in 0..n {
-            correlation += signal[i] * signal[i + lag];
+            for target in 0..bins {
+                let low = target.saturating_sub(band);
+                let high = (target + band).min(bins - 1);
+
+                let mut best_voiced = (f32::NEG_INFINITY, 0usize);
+                let mut best_unvoiced = (f32::NEG_INFINITY, 0usize);
+                for source in low..=high {
+                    let transition = log_transition(source, target);
+                    let voiced = scores[source] + transition;
+                    if voiced > best_voiced.0 {
+                        best_voiced = (voiced, source);
+                    }
+                    let unvoiced = scores[bins + source] + transition;
+                    if unvoiced > best_unvoiced.0 {
+                        best_unvoiced = (unvoiced, source);
+                    }
+                }
+
+                let encode = |source: usize, from_unvoiced: bool| {
+                    (source + band - target) as u8 | if from_unvoiced { 0x80 } else { 0 }
+                };
+
+                // Into the voiced state
+                let (score, pointer) = if best_voiced.0 + log_stay >= best_unvoiced.0 + log_switch {
+                    (best_voiced.0 + log_stay, encode(best_voiced.1, false))
+                } else {
+                    (best_unvoiced.0 + log_switch, encode(best_unvoiced.1, true))
+                };
+                next_scores[target] = score + observation[target];
+                pointers[target] = pointer;
+
+                // Into the unvoiced state
+                let (score, pointer) = if best_unvoiced.0 + log_stay >= best_voiced.0 + log_switch {
+                    (best_unvoiced.0 + log_stay, encode(best_unvoiced.1, true))
+                } else {
+                    (best_voiced.0 + log_switch, encode(best_voiced.1, false))
+                };
+                next_scores[bins + target] = score + observation[bins + target];
+                pointers[bins + target] = pointer;
+            }
+
+            std::mem::swap(&mut scores, &mut next_scores);
         }
 
-        correlation / n as f32
+        // Trace the best path back from the final frame
+        let mut state = scores
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(state, _)| state)
+            .unwrap_or(bins);
+        let mut path = vec![0usize; candidates.len()];
+        for t in (0..candidates.len()).rev() {
+            path[t] = state;
+            if t > 0 {
+                let pointer = backpointers[t * 2 * bins + state];
+                let bin = state % bins;
+                let source = bin + (pointer & 0x7f) as usize - band;
+                state = if pointer & 0x80 != 0 { bins + source } else { source };
+            }
+        }
+
+        path.iter()
+            .zip(candidates)
+            .map(|(&state, frame)| {
+                let voicing = frame.iter().map(|c| c.value).sum::<f32>().clamp(0.0, 1.0);
+                if state >= bins {
+                    return PitchFrame { frequency: 0.0, voicing };
+                }
+
+                // Report the refined candidate frequency when one fell in the chosen bin
+                let frequency = frame
+                    .iter()
+                    .map(|c| self.sample_rate as f32 / c.period)
+                    .find(|&f| bin_of(f) == state)
+                    .unwrap_or_else(|| frequency_of(state));
+                PitchFrame { frequency, voicing }
+            })
+            .collect()
     }
-}
\ No newline at end of file
+}
+
+/// CDF of the Beta(2, b) distribution
+fn beta_2_b_cdf(x: f32, b: i32) -> f32 {
+    let x = x.clamp(0.0, 1.0);
+    1.0 - (1.0 - x).powi(b + 1) - (b + 1) as f32 * x * (1.0 - x).powi(b)
+}
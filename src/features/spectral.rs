@@ -1,8 +1,73 @@
 use rustfft::{FftPlanner, num_complex::Complex};
+use std::f32::consts::PI;
+
+use super::FrameConfig;
+
+/// Magnitudes are clamped to this before taking logarithms
+const MAGNITUDE_FLOOR: f32 = 1e-6;
+
+/// Magnitude spectrum summarised over a whole recording
+#[derive(Debug, Clone, PartialEq)]
+pub struct LongTermSpectrum {
+    /// Arithmetic mean magnitude of each bin
+    pub magnitudes: Vec<f32>,
+    /// Geometric mean magnitude of each bin. A component has to be present in
+    /// nearly every frame to keep its level here, so a drone stands out while
+    /// the moving melody line is suppressed.
+    pub sustained: Vec<f32>,
+    /// Width of one bin in Hz
+    pub bin_hz: f32,
+}
+
+impl LongTermSpectrum {
+    /// Interpolated peaks of the sustained spectrum between `min_hz` and
+    /// `max_hz` that rise above `prominence` times the median of that range, as
+    /// (Hz, magnitude)
+    pub fn peaks(&self, min_hz: f32, max_hz: f32, prominence: f32) -> Vec<(f32, f32)> {
+        let spectrum = &self.sustained;
+        let first = ((min_hz / self.bin_hz).floor() as usize).max(1);
+        let last = ((max_hz / self.bin_hz).ceil() as usize).min(spectrum.len().saturating_sub(2));
+        if first >= last {
+            return Vec::new();
+        }
+
+        let mut range: Vec<f32> = spectrum[first..=last].to_vec();
+        range.sort_by(|a, b| a.total_cmp(b));
+        let floor = range[range.len() / 2] * prominence;
+
+        (first..=last)
+            .filter(|&bin| {
+                let m = spectrum[bin];
+                m > floor && m > spectrum[bin - 1] && m >= spectrum[bin + 1]
+            })
+            .map(|bin| {
+                let (left, centre, right) = (spectrum[bin - 1], spectrum[bin], spectrum[bin + 1]);
+                let curvature = left - 2.0 * centre + right;
+                let shift = if curvature.abs() > f32::EPSILON {
+                    (0.5 * (left - right) / curvature).clamp(-0.5, 0.5)
+                } else {
+                    0.0
+                };
+                ((bin as f32 + shift) * self.bin_hz, centre)
+            })
+            .collect()
+    }
+
+    /// Ratio of sustained to mean magnitude at `hz`, in [0, 1]: close to 1 for
+    /// components that never stop, close to 0 for ones heard only now and then
+    pub fn steadiness(&self, hz: f32) -> f32 {
+        let bin = ((hz / self.bin_hz).round() as usize).min(self.magnitudes.len().saturating_sub(1));
+        match self.magnitudes.get(bin) {
+            Some(&mean) if mean > 0.0 => (self.sustained[bin] / mean).clamp(0.0, 1.0),
+            _ => 0.0,
+        }
+    }
+}
 
 pub struct SpectralAnalyzer {
     sample_rate: u32,
     fft_size: usize,
+    frame_config: FrameConfig,
 }
 
 impl SpectralAnalyzer {
@@ -10,9 +75,87 @@ impl SpectralAnalyzer {
         Self {
             sample_rate,
             fft_size,
+            frame_config: FrameConfig::new(fft_size, fft_size / 4),
+        }
+    }
+
+    /// Override the hop and padding; the window length always equals the FFT size
+    pub fn with_frame_config(mut self, frame_config: FrameConfig) -> Self {
+        self.frame_config = FrameConfig {
+            window_size: self.fft_size,
+            ..frame_config
+        };
+        self
+    }
+
+    pub fn frame_config(&self) -> &FrameConfig {
+        &self.frame_config
+    }
+
+    /// Hann-windowed magnitude spectrum of every frame
+    pub fn magnitude_spectra(&self, samples: &[f32]) -> Vec<Vec<f32>> {
+        let mut planner = FftPlanner::new();
+        let fft = planner.plan_fft_forward(self.fft_size);
+
+        self.frame_config
+            .frames(samples)
+            .map(|window| self.magnitude_spectrum(&window, &fft))
+            .collect()
+    }
+
+    /// Mean and sustained (geometric mean) magnitude spectra over the whole signal
+    pub fn long_term_spectrum(&self, samples: &[f32]) -> LongTermSpectrum {
+        let mut planner = FftPlanner::new();
+        let fft = planner.plan_fft_forward(self.fft_size);
+
+        let mut sums = vec![0.0f64; self.fft_size / 2];
+        let mut log_sums = vec![0.0f64; self.fft_size / 2];
+        let mut frames = 0usize;
+        for window in self.frame_config.frames(samples) {
+            for (bin, magnitude) in self.magnitude_spectrum(&window, &fft).into_iter().enumerate() {
+                sums[bin] += magnitude as f64;
+                log_sums[bin] += (magnitude.max(MAGNITUDE_FLOOR) as f64).ln();
+            }
+            frames += 1;
+        }
+
+        let count = frames.max(1) as f64;
+        let magnitudes = sums.iter().map(|&total| (total / count) as f32).collect();
+        let sustained = if frames > 0 {
+            log_sums.iter().map(|&total| (total / count).exp() as f32).collect()
+        } else {
+            vec![0.0; self.fft_size / 2]
+        };
+
+        LongTermSpectrum {
+            magnitudes,
+            sustained,
+            bin_hz: self.sample_rate as f32 / self.fft_size as f32,
         }
     }
 
+    fn magnitude_spectrum(&self, window: &[f32], fft: &std::sync::Arc<dyn rustfft::Fft<f32>>) -> Vec<f32> {
+        let mut spectrum: Vec<Complex<f32>> = window
+            .iter()
+            .enumerate()
+            .map(|(i, &sample)| {
+                let window_val = 0.5 * (1.0 - (2.0 * PI * i as f32 / window.len() as f32).cos());
+                Complex::new(sample * window_val, 0.0)
+            })
+            .collect();
+        fft.process(&mut spectrum);
+
+        spectrum.iter().take(self.fft_size / 2).map(|c| c.norm()).collect()
+    }
+
+    /// Spectral centroid of every frame
+    pub fn spectral_centroids(&self, samples: &[f32]) -> Vec<f32> {
+        self.magnitude_spectra(samples)
+            .iter()
+            .map(|spectrum| self.spectral_centroid(spectrum))
+            .collect()
+    }
+
     pub fn spectral_centroid(&self, spectrum: &[f32]) -> f32 {
         let mut weighted_sum = 0.0;
         let mut magnitude_sum = 0.0;
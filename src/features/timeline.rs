@@ -0,0 +1,109 @@
+use super::FrameConfig;
+
+/// Frame layout of one feature stream, used to map frame indices to time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StreamInfo {
+    /// Sample rate of the signal the stream was extracted from
+    pub sample_rate: u32,
+    /// Window, hop and padding used to cut the signal into frames
+    pub frame_config: FrameConfig,
+    /// Position of the signal's first sample in the original recording, in seconds
+    pub start_time: f64,
+}
+
+impl StreamInfo {
+    pub fn new(sample_rate: u32, frame_config: FrameConfig, start_time: f64) -> Self {
+        Self {
+            sample_rate,
+            frame_config,
+            start_time,
+        }
+    }
+
+    /// Frames per second
+    pub fn frame_rate(&self) -> f64 {
+        self.sample_rate as f64 / self.frame_config.hop_size as f64
+    }
+
+    /// Duration of one hop in seconds
+    pub fn hop_duration(&self) -> f64 {
+        self.frame_config.hop_size as f64 / self.sample_rate as f64
+    }
+
+    /// Time of the centre of frame `index` in the original recording
+    pub fn time_of(&self, index: usize) -> f64 {
+        self.start_time + self.frame_config.frame_time(index, self.sample_rate)
+    }
+
+    /// Index of the frame whose centre is closest to `time` (which may be out of range)
+    pub fn index_at(&self, time: f64) -> usize {
+        let first_centre = self.time_of(0);
+        ((time - first_centre) / self.hop_duration()).round().max(0.0) as usize
+    }
+}
+
+/// A sequence of per-frame feature values together with its timing
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureStream<T> {
+    pub info: StreamInfo,
+    pub frames: Vec<T>,
+}
+
+impl<T> FeatureStream<T> {
+    pub fn new(info: StreamInfo, frames: Vec<T>) -> Self {
+        Self { info, frames }
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Centre times of all frames
+    pub fn times(&self) -> Vec<f64> {
+        (0..self.frames.len()).map(|index| self.info.time_of(index)).collect()
+    }
+
+    /// Frames paired with their centre times
+    pub fn iter_timed(&self) -> impl Iterator<Item = (f64, &T)> + '_ {
+        self.frames
+            .iter()
+            .enumerate()
+            .map(|(index, frame)| (self.info.time_of(index), frame))
+    }
+
+    /// The frame closest to `time`, if `time` lies within the stream
+    pub fn at_time(&self, time: f64) -> Option<&T> {
+        let half_hop = self.info.hop_duration() / 2.0;
+        let last = self.frames.len().checked_sub(1)?;
+        if time < self.info.time_of(0) - half_hop || time > self.info.time_of(last) + half_hop {
+            return None;
+        }
+        self.frames.get(self.info.index_at(time).min(last))
+    }
+
+    /// Frames whose centres fall within `[start, end)` seconds
+    pub fn slice_time(&self, start: f64, end: f64) -> &[T] {
+        // Index of the first frame centred at or after `time`, allowing for
+        // rounding when `time` is itself a frame time
+        let first_at_or_after = |time: f64| {
+            let position = (time - self.info.time_of(0)) / self.info.hop_duration();
+            let index = (position - 1e-9).ceil().max(0.0) as usize;
+            index.min(self.frames.len())
+        };
+
+        let (first, last) = (first_at_or_after(start), first_at_or_after(end));
+        &self.frames[first.min(last)..last]
+    }
+}
+
+/// Format seconds as `HH:MM:SS.s`, e.g. `00:12:34.5`
+pub fn format_timestamp(seconds: f64) -> String {
+    let tenths = (seconds.max(0.0) * 10.0).round() as u64;
+    let (hours, rest) = (tenths / 36000, tenths % 36000);
+    let (minutes, rest) = (rest / 600, rest % 600);
+    format!("{:02}:{:02}:{:02}.{}", hours, minutes, rest / 10, rest % 10)
+}
@@ -0,0 +1,18 @@
+//! Hindustani raag detection.
+//!
+//! The crate is organised as a pipeline: [`audio`] decodes and preprocesses
+//! recordings, [`features`] extracts pitch and chroma features, and
+//! [`classification`] matches them against a database of raags described in
+//! terms of the swaras of [`theory`].
+//! [`RaagDetector`] wires the stages together.
+
+pub mod audio;
+pub mod classification;
+pub mod error;
+pub mod features;
+pub mod theory;
+
+mod detector;
+
+pub use detector::{Detection, DetectorConfig, RaagDetector};
+pub use error::{RaagError, Result};
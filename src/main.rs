@@ -1,14 +1,16 @@
-mod audio;
-mod features;
-mod classification;
-
 use anyhow::Result;
 use clap::Parser;
-use std::path::PathBuf;
+use hound::{SampleFormat, WavSpec, WavWriter};
+use std::path::{Path, PathBuf};
 
-use audio::AudioReader;
-use features::{PitchExtractor, ChromagramExtractor, SpectralAnalyzer};
-use classification::{RaagClassifier, AudioFeatures};
+use raag_detection::audio::{DownmixStrategy, ReadOptions, SeparationConfig, ANALYSIS_SAMPLE_RATE};
+use raag_detection::classification::{
+    parse_tonic, DistanceMetric, RaagDatabase, TonicSource, DEFAULT_DIRECTION_WEIGHT, DEFAULT_ORNAMENT_WEIGHT,
+    DEFAULT_PHRASE_WEIGHT,
+};
+use raag_detection::features::format_timestamp;
+use raag_detection::theory::{Swara, TuningSystem};
+use raag_detection::{DetectorConfig, RaagDetector};
 
 #[derive(Parser)]
 #[command(name = "raag-detection")]
@@ -19,45 +21,215 @@ struct Args {
 
     #[arg(short, long, help = "Output detailed analysis")]
     verbose: bool,
+
+    #[arg(long, default_value_t = 0.0, help = "Seconds to skip from the start of the recording")]
+    offset: f32,
+
+    #[arg(long, help = "Maximum number of seconds to analyze (default: whole file)")]
+    max_duration: Option<f32>,
+
+    #[arg(long, default_value = "average", help = "How to mix channels down to mono: average, left, right, max-energy")]
+    downmix: DownmixStrategy,
+
+    #[arg(long, default_value_t = ANALYSIS_SAMPLE_RATE, value_parser = clap::value_parser!(u32).range(1..), help = "Sample rate (Hz) audio is resampled to before feature extraction")]
+    analysis_rate: u32,
+
+    #[arg(long, help = "Analyze at the file's native sample rate instead of resampling")]
+    native_rate: bool,
+
+    #[arg(long, default_value = "bhattacharyya", help = "Distance between pitch distributions: kl, bhattacharyya, cosine")]
+    metric: DistanceMetric,
+
+    #[arg(long, default_value_t = 12, help = "Bins per octave of the pitch-class distribution (e.g. 120 for 10-cent bins)")]
+    pcd_resolution: usize,
+
+    #[arg(long, default_value_t = 3, help = "Number of candidate raags to list")]
+    top: usize,
+
+    #[arg(long, help = "Largest distance from the best raag's template at which it is still reported (default depends on --metric)")]
+    max_distance: Option<f32>,
+
+    #[arg(long, default_value_t = DEFAULT_DIRECTION_WEIGHT, help = "Weight (0-1) of ascending/descending movement against the aroha/avaroha")]
+    direction_weight: f32,
+
+    #[arg(long, default_value_t = DEFAULT_PHRASE_WEIGHT, help = "How strongly hearing a raag's characteristic phrases raises its share (0 to ignore)")]
+    phrase_weight: f32,
+
+    #[arg(long, default_value_t = DEFAULT_ORNAMENT_WEIGHT, help = "How strongly hearing a raag's characteristic ornaments raises its share (0 to ignore)")]
+    ornament_weight: f32,
+
+    #[arg(long, value_parser = parse_tonic, help = "Known tonic (Sa) in Hz or as a note with optional cents, e.g. 138.6, C#, C#+25c")]
+    tonic: Option<f32>,
+
+    #[arg(long, default_value = "just", help = "Tuning of raag notes: et, just, shruti, or 12 comma-separated cent values from Sa")]
+    tuning: TuningSystem,
+
+    #[arg(long, help = "Separate the lead melody from tabla and tanpura before pitch tracking")]
+    separate: bool,
+
+    #[arg(long, default_value = "P", help = "Drone string tuned besides Sa, e.g. P, m or N (with --separate)")]
+    drone: Swara,
+
+    #[arg(long, help = "Write the separated lead melody to this WAV file (implies --separate)")]
+    write_lead: Option<PathBuf>,
+
+    #[arg(long, help = "Print the steady notes as sargam with onset and offset times")]
+    transcribe: bool,
+
+    #[arg(long, help = "Print the meends, oscillations and kans found, with onset and offset times")]
+    ornaments: bool,
+
+    #[arg(long, help = "JSON or TOML file of raag definitions, added to (or overriding) the bundled ones")]
+    raag_db: Option<PathBuf>,
+
+    #[arg(long, requires = "raag_db", help = "Use only the raags from --raag-db, not the bundled catalogue")]
+    no_default_raags: bool,
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
 
+    let mut database = if args.no_default_raags {
+        RaagDatabase::from_raags(Vec::new())
+    } else {
+        RaagDatabase::new()
+    };
+    if let Some(path) = &args.raag_db {
+        database = database.merge_validated(RaagDatabase::from_path(path)?)?;
+        for warning in database.validate() {
+            eprintln!("{}", warning);
+        }
+    }
+
+    let detector = RaagDetector::new(DetectorConfig {
+        read_options: ReadOptions {
+            offset: args.offset,
+            max_duration: args.max_duration,
+        },
+        downmix: args.downmix,
+        analysis_rate: (!args.native_rate).then_some(args.analysis_rate),
+        metric: args.metric,
+        pcd_resolution: args.pcd_resolution,
+        max_distance: args.max_distance,
+        direction_weight: args.direction_weight,
+        phrase_weight: args.phrase_weight,
+        ornament_weight: args.ornament_weight,
+        tonic: args.tonic,
+        tuning: args.tuning,
+        separation: (args.separate || args.write_lead.is_some()).then(|| SeparationConfig {
+            drone_swara: args.drone,
+            ..SeparationConfig::default()
+        }),
+        ..DetectorConfig::default()
+    })
+    .with_database(database);
+
     println!("Analyzing audio file: {}", args.audio_file.display());
 
     // Read audio file
-    let audio = AudioReader::from_file(&args.audio_file)?;
+    let audio = detector.load(&args.audio_file)?;
     println!("Audio loaded: {:.2}s, {} Hz, {} channels",
              audio.duration_seconds(),
              audio.sample_rate,
              audio.channels);
 
-    // Extract features
-    let pitch_extractor = PitchExtractor::new(audio.sample_rate);
-    let chroma_extractor = ChromagramExtractor::new(audio.sample_rate, 2048);
-    let spectral_analyzer = SpectralAnalyzer::new(audio.sample_rate, 2048);
-
-    let pitch_contour = pitch_extractor.extract_f0(&audio.samples);
-    let chromagram = chroma_extractor.extract_chromagram(&audio.samples);
+    // Extract features and classify raag
+    let detection = detector.detect(&audio)?;
 
     if args.verbose {
-        println!("Extracted {} pitch frames", pitch_contour.len());
-        println!("Extracted {} chroma frames", chromagram.len());
+        if detection.analysis_rate != audio.sample_rate {
+            println!("Resampled {} Hz -> {} Hz for analysis", audio.sample_rate, detection.analysis_rate);
+        }
+        let pitch = &detection.features.pitch_contour;
+        let voiced = pitch.frames.iter().filter(|f| f.is_voiced()).count();
+        println!("Extracted {} pitch frames ({} voiced, {:.1} frames/s)", pitch.len(), voiced, pitch.info.frame_rate());
+        if let Some((time, _)) = pitch.iter_timed().find(|(_, f)| f.is_voiced()) {
+            println!("First voiced frame at {}", format_timestamp(time));
+        }
+        println!("Extracted {} chroma frames", detection.features.chromagram.len());
     }
 
-    // Classify raag
-    let features = AudioFeatures {
-        pitch_contour,
-        chromagram,
-        spectral_centroid: vec![], // Placeholder
-    };
+    if let (Some(path), Some(lead)) = (&args.write_lead, &detection.lead) {
+        write_wav(path, lead, detection.analysis_rate)?;
+        println!("Wrote lead melody to {}", path.display());
+    }
+
+    let result = &detection.classification;
+    match result.tonic.source {
+        TonicSource::UserSupplied => println!("Tonic (Sa): {:.1} Hz (user supplied)", result.tonic.frequency),
+        _ => println!(
+            "Estimated tonic (Sa): {:.1} Hz (confidence {:.2}, {})",
+            result.tonic.frequency, result.tonic.confidence, result.tonic.source
+        ),
+    }
+
+    if args.verbose && !result.transcription.is_empty() {
+        println!("Transcription: {}", result.transcription);
+    }
+    if args.transcribe {
+        for note in &result.transcription.notes {
+            println!("{} - {}  {}", format_timestamp(note.onset), format_timestamp(note.offset), note);
+        }
+    }
+    if args.ornaments {
+        for ornament in &result.ornaments {
+            println!("{} - {}  {}", format_timestamp(ornament.onset), format_timestamp(ornament.offset), ornament);
+        }
+    }
 
-    let classifier = RaagClassifier::new();
-    match classifier.classify(&features)? {
-        Some(raag) => println!("Detected Raag: {}", raag),
+    match result.best() {
+        Some(best) => println!("Detected Raag: {} (confidence {:.2})", best.name, best.score),
         None => println!("Could not identify raag"),
     }
 
+    let candidates: Vec<String> = result
+        .top(args.top)
+        .iter()
+        .map(|candidate| format!("{} {:.2}", candidate.name, candidate.share))
+        .collect();
+    println!("Candidates: {}", candidates.join(", "));
+
+    if args.verbose {
+        for candidate in result.top(args.top) {
+            let direction = |distance: Option<f32>| distance.map_or("-".to_string(), |d| format!("{:.3}", d));
+            println!("  {}: distance {:.3}, vadi {:.2}, samvadi {:.2}, aroha {}, avaroha {}, phrases {:.2}, ornaments {:.2}",
+                     candidate.name,
+                     candidate.distance,
+                     candidate.evidence.vadi_strength,
+                     candidate.evidence.samvadi_strength,
+                     direction(candidate.evidence.aroha_distance),
+                     direction(candidate.evidence.avaroha_distance),
+                     candidate.evidence.phrase_score,
+                     candidate.evidence.ornament_score);
+            let Some(raag) = detector.database().find_raag(&candidate.name) else {
+                continue;
+            };
+            for found in &candidate.evidence.phrase_matches {
+                let notes: Vec<String> = raag.characteristic_phrases[found.phrase].notes.iter().map(|n| n.to_string()).collect();
+                println!("    {} at {} - {} ({:.2})",
+                         notes.join(" "),
+                         format_timestamp(found.start),
+                         format_timestamp(found.end),
+                         found.score);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Save a mono signal as 32-bit float WAV
+fn write_wav(path: &Path, samples: &[f32], sample_rate: u32) -> Result<()> {
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: SampleFormat::Float,
+    };
+    let mut writer = WavWriter::create(path, spec)?;
+    for &sample in samples {
+        writer.write_sample(sample)?;
+    }
+    writer.finalize()?;
     Ok(())
 }
@@ -0,0 +1,7 @@
+//! Swaras, octaves and the tuning systems that give them pitches.
+
+pub mod swara;
+pub mod tuning;
+
+pub use swara::{Notation, Note, Octave, Swara};
+pub use tuning::{Intonation, Shruti, TuningSystem};
@@ -0,0 +1,424 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::TuningSystem;
+
+/// One of the twelve swaras of an octave
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Swara {
+    Sa,
+    KomalRe,
+    Re,
+    KomalGa,
+    Ga,
+    Ma,
+    TivraMa,
+    Pa,
+    KomalDha,
+    Dha,
+    KomalNi,
+    Ni,
+}
+
+impl Swara {
+    pub const ALL: [Swara; 12] = [
+        Swara::Sa,
+        Swara::KomalRe,
+        Swara::Re,
+        Swara::KomalGa,
+        Swara::Ga,
+        Swara::Ma,
+        Swara::TivraMa,
+        Swara::Pa,
+        Swara::KomalDha,
+        Swara::Dha,
+        Swara::KomalNi,
+        Swara::Ni,
+    ];
+
+    /// Position in the octave, 0 (Sa) to 11 (shuddha Ni)
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// The swara `semitones` above Sa, folded into one octave
+    pub fn from_index(semitones: i32) -> Swara {
+        Swara::ALL[semitones.rem_euclid(12) as usize]
+    }
+
+    /// Single-letter sargam: upper case for shuddha, lower case for komal,
+    /// `M` for tivra Ma
+    pub fn sargam(self) -> &'static str {
+        ["S", "r", "R", "g", "G", "m", "M", "P", "d", "D", "n", "N"][self.index()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Swara::Sa => "Sa",
+            Swara::KomalRe => "komal Re",
+            Swara::Re => "Re",
+            Swara::KomalGa => "komal Ga",
+            Swara::Ga => "Ga",
+            Swara::Ma => "Ma",
+            Swara::TivraMa => "tivra Ma",
+            Swara::Pa => "Pa",
+            Swara::KomalDha => "komal Dha",
+            Swara::Dha => "Dha",
+            Swara::KomalNi => "komal Ni",
+            Swara::Ni => "Ni",
+        }
+    }
+
+    /// Apply a komal or tivra modifier to a shuddha swara, if that variant exists
+    fn altered(self, alteration: Alteration) -> Option<Swara> {
+        match (alteration, self) {
+            (Alteration::Shuddha, swara) => Some(swara),
+            (Alteration::Komal, Swara::Re | Swara::Ga | Swara::Dha | Swara::Ni) => {
+                Some(Swara::from_index(self.index() as i32 - 1))
+            }
+            (Alteration::Tivra, Swara::Ma) => Some(Swara::TivraMa),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Swara {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Accepts the same spellings as a madhya-octave `Note`, e.g. `g` or `komal Ga`
+impl FromStr for Swara {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let note: Note = s.parse()?;
+        if note.octave != Octave::Madhya {
+            return Err(format!("Expected a swara without octave marks: {}", s));
+        }
+        Ok(note.swara)
+    }
+}
+
+/// Written as its sargam letter, so swaras can key maps in definition files
+impl Serialize for Swara {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.sargam())
+    }
+}
+
+impl<'de> Deserialize<'de> for Swara {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Register of a note: lower (mandra), middle (madhya) or upper (taar) saptak
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub enum Octave {
+    Mandra,
+    #[default]
+    Madhya,
+    Taar,
+}
+
+impl Octave {
+    /// Octaves above (positive) or below (negative) the middle register
+    pub fn offset(self) -> i32 {
+        match self {
+            Octave::Mandra => -1,
+            Octave::Madhya => 0,
+            Octave::Taar => 1,
+        }
+    }
+
+    pub fn from_offset(offset: i32) -> Option<Octave> {
+        match offset {
+            -1 => Some(Octave::Mandra),
+            0 => Some(Octave::Madhya),
+            1 => Some(Octave::Taar),
+            _ => None,
+        }
+    }
+}
+
+/// How a note is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notation {
+    /// Roman sargam: letters (`S r R g G m M P d D n N`) or names (`Sa`,
+    /// `komal Re`, `Re(k)`, `Ma#`, `tivra Ma`, `Ni♭`); `.N` or `N.` for mandra,
+    /// `S'` for taar
+    Sargam,
+    /// Devanagari as in Bhatkhande's notation: `सा रे ग म प ध नि`, komal marked
+    /// by an underline, tivra by a vertical stroke above, mandra by a dot
+    /// below and taar by a dot above (or anusvara, as in `सां`)
+    Bhatkhande,
+    /// Western note names with Sa = C: `C`, `Db`, `F#`; an octave number of 3,
+    /// 4 or 5 selects mandra, madhya or taar
+    Western,
+}
+
+/// A swara in a particular octave
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Note {
+    pub swara: Swara,
+    pub octave: Octave,
+}
+
+impl Note {
+    pub fn new(swara: Swara, octave: Octave) -> Self {
+        Self { swara, octave }
+    }
+
+    /// Semitones above madhya Sa; orders notes by pitch
+    pub fn semitones(&self) -> i32 {
+        self.octave.offset() * 12 + self.swara.index() as i32
+    }
+
+    /// The note `semitones` above madhya Sa, if it lies within the three octaves
+    pub fn from_semitones(semitones: i32) -> Option<Note> {
+        let octave = Octave::from_offset(semitones.div_euclid(12))?;
+        Some(Note::new(Swara::from_index(semitones), octave))
+    }
+
+    /// The note nearest to a frequency ratio to madhya Sa (e.g. `1.5` is Pa)
+    pub fn nearest(ratio: f32) -> Option<Note> {
+        if !(ratio.is_finite() && ratio > 0.0) {
+            return None;
+        }
+        Note::from_semitones((12.0 * ratio.log2()).round() as i32)
+    }
+
+    /// Cents above madhya Sa under `tuning`
+    pub fn cents(&self, tuning: &TuningSystem) -> f32 {
+        tuning.cents(self.swara) + 1200.0 * self.octave.offset() as f32
+    }
+
+    /// Frequency ratio to madhya Sa under `tuning`
+    pub fn ratio(&self, tuning: &TuningSystem) -> f32 {
+        2f32.powf(self.cents(tuning) / 1200.0)
+    }
+
+    pub fn parse(s: &str, notation: Notation) -> Result<Note, String> {
+        let parsed = match notation {
+            Notation::Sargam => parse_sargam(s.trim()),
+            Notation::Bhatkhande => parse_bhatkhande(s.trim()),
+            Notation::Western => parse_western(s.trim()),
+        };
+        parsed.ok_or_else(|| format!("Invalid {:?} note: {}", notation, s))
+    }
+
+    /// Parse a space-separated sequence of notes, e.g. `"S R G M P D N S'"`
+    pub fn parse_sequence(s: &str) -> Result<Vec<Note>, String> {
+        s.split_whitespace().map(str::parse).collect()
+    }
+}
+
+/// Accepts sargam or Bhatkhande notation; Western names need `Note::parse`
+/// since letters like `D` and `G` mean different notes there
+impl FromStr for Note {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        parse_sargam(s)
+            .or_else(|| parse_bhatkhande(s))
+            .ok_or_else(|| format!("Invalid note: {} (expected sargam like S, r, M, .N, S' or a name like komal Re)", s))
+    }
+}
+
+/// Sargam letter with octave marks: `r`, `.N`, `S'`
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.octave {
+            Octave::Mandra => write!(f, ".{}", self.swara.sargam()),
+            Octave::Madhya => write!(f, "{}", self.swara.sargam()),
+            Octave::Taar => write!(f, "{}'", self.swara.sargam()),
+        }
+    }
+}
+
+impl Serialize for Note {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Note {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match NoteRepr::deserialize(deserializer)? {
+            NoteRepr::Text(text) => text.parse().map_err(serde::de::Error::custom),
+            NoteRepr::Ratio { frequency_ratio } => Note::nearest(frequency_ratio)
+                .ok_or_else(|| serde::de::Error::custom(format!("frequency ratio {} is out of range", frequency_ratio))),
+        }
+    }
+}
+
+/// A note as written in a definition file: notation text, or the older
+/// `{ name, frequency_ratio }` form (the ratio is snapped to the nearest swara)
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NoteRepr {
+    Text(String),
+    Ratio { frequency_ratio: f32 },
+}
+
+/// Serde helpers that write a list of notes as one sargam string and read
+/// either such a string or a list of individual notes
+pub mod note_list {
+    use super::Note;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NotesRepr {
+        Text(String),
+        List(Vec<Note>),
+    }
+
+    pub fn serialize<S: Serializer>(notes: &[Note], serializer: S) -> Result<S::Ok, S::Error> {
+        let text: Vec<String> = notes.iter().map(Note::to_string).collect();
+        serializer.serialize_str(&text.join(" "))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Note>, D::Error> {
+        match NotesRepr::deserialize(deserializer)? {
+            NotesRepr::Text(text) => Note::parse_sequence(&text).map_err(serde::de::Error::custom),
+            NotesRepr::List(notes) => Ok(notes),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Alteration {
+    Shuddha,
+    Komal,
+    Tivra,
+}
+
+/// Strip octave marks from both ends, returning the core and the octave
+fn split_octave(s: &str) -> Option<(&str, Octave)> {
+    let mut core = s;
+    let mut offset = 0;
+    if let Some(rest) = core.strip_prefix('.') {
+        core = rest;
+        offset -= 1;
+    }
+    if let Some(rest) = core.strip_suffix(['.', ',']) {
+        core = rest;
+        offset -= 1;
+    } else if let Some(rest) = core.strip_suffix(['\'', '’', '*']) {
+        core = rest;
+        offset += 1;
+    }
+    Some((core, Octave::from_offset(offset)?))
+}
+
+fn parse_sargam(s: &str) -> Option<Note> {
+    let (core, octave) = split_octave(s)?;
+
+    // Single letters are case-sensitive; `m^` is also read as tivra Ma
+    let letter = match core {
+        "m^" | "M^" => Some(Swara::TivraMa),
+        _ if core.len() == 1 => "SrRgGmMPdDnN".find(core).map(|i| Swara::ALL[i]),
+        _ => None,
+    };
+    if let Some(swara) = letter {
+        return Some(Note::new(swara, octave));
+    }
+
+    let mut name = core.to_lowercase();
+    let mut alteration = Alteration::Shuddha;
+    for (marker, marked) in [
+        ("komal ", Alteration::Komal),
+        ("tivra ", Alteration::Tivra),
+        ("teevra ", Alteration::Tivra),
+        ("shuddha ", Alteration::Shuddha),
+    ] {
+        if let Some(rest) = name.strip_prefix(marker) {
+            alteration = marked;
+            name = rest.trim().to_string();
+        }
+    }
+    for (marker, marked) in [
+        ("(k)", Alteration::Komal),
+        ("♭", Alteration::Komal),
+        ("b", Alteration::Komal),
+        ("(t)", Alteration::Tivra),
+        ("#", Alteration::Tivra),
+        ("♯", Alteration::Tivra),
+    ] {
+        if let Some(rest) = name.strip_suffix(marker) {
+            alteration = marked;
+            name = rest.trim().to_string();
+            break;
+        }
+    }
+
+    let swara = match name.as_str() {
+        "sa" => Swara::Sa,
+        "re" | "ri" => Swara::Re,
+        "ga" => Swara::Ga,
+        "ma" => Swara::Ma,
+        "pa" => Swara::Pa,
+        "dha" | "da" => Swara::Dha,
+        "ni" => Swara::Ni,
+        _ => return None,
+    };
+    Some(Note::new(swara.altered(alteration)?, octave))
+}
+
+fn parse_bhatkhande(s: &str) -> Option<Note> {
+    let mut alteration = Alteration::Shuddha;
+    let mut offset = 0;
+    let mut core = String::new();
+    for c in s.chars() {
+        match c {
+            '\u{0332}' | '_' => alteration = Alteration::Komal,
+            '\u{030D}' | '\u{0951}' => alteration = Alteration::Tivra,
+            '\u{0323}' | '\u{093C}' => offset -= 1,
+            '\u{0307}' | '\u{0902}' => offset += 1,
+            _ => core.push(c),
+        }
+    }
+
+    let swara = match core.as_str() {
+        "सा" | "स" => Swara::Sa,
+        "रे" | "र" => Swara::Re,
+        "ग" => Swara::Ga,
+        "म" => Swara::Ma,
+        "प" => Swara::Pa,
+        "ध" => Swara::Dha,
+        "नि" | "न" => Swara::Ni,
+        _ => return None,
+    };
+    Some(Note::new(swara.altered(alteration)?, Octave::from_offset(offset)?))
+}
+
+fn parse_western(s: &str) -> Option<Note> {
+    let mut chars = s.chars();
+    let letter = chars.next()?.to_ascii_uppercase();
+    let base = match letter {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+
+    let rest = chars.as_str();
+    let (accidental, octave) = match rest.chars().next() {
+        Some('#' | '♯') => (1, &rest[rest.chars().next()?.len_utf8()..]),
+        Some('b' | '♭') => (-1, &rest[rest.chars().next()?.len_utf8()..]),
+        _ => (0, rest),
+    };
+    let octave: i32 = if octave.is_empty() { 0 } else { octave.parse::<i32>().ok()? - 4 };
+
+    Note::from_semitones(octave * 12 + base + accidental)
+}
@@ -0,0 +1,333 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::Swara;
+
+/// Just ratios of the twelve swaras, as used by the bundled raag definitions
+const JUST_RATIOS: [(u32, u32); 12] = [
+    (1, 1),
+    (16, 15),
+    (9, 8),
+    (6, 5),
+    (5, 4),
+    (4, 3),
+    (45, 32),
+    (3, 2),
+    (8, 5),
+    (27, 16),
+    (16, 9),
+    (15, 8),
+];
+
+/// Ratios to Sa of the 22 shrutis, in the order of `Shruti::ALL`
+const SHRUTI_RATIOS: [(u32, u32); 22] = [
+    (1, 1),
+    (256, 243),
+    (16, 15),
+    (10, 9),
+    (9, 8),
+    (32, 27),
+    (6, 5),
+    (5, 4),
+    (81, 64),
+    (4, 3),
+    (27, 20),
+    (45, 32),
+    (729, 512),
+    (3, 2),
+    (128, 81),
+    (8, 5),
+    (5, 3),
+    (27, 16),
+    (16, 9),
+    (9, 5),
+    (15, 8),
+    (243, 128),
+];
+
+/// A custom table entry may stray this far from the equal-tempered swara
+/// before it counts as a different swara
+const MAX_SWARA_DEVIATION_CENTS: f32 = 50.0;
+
+/// One of the 22 shrutis (microtonal positions) of the octave, from Sa upwards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Shruti {
+    Kshobhini,
+    Tivra,
+    Kumudvati,
+    Manda,
+    Chandovati,
+    Dayavati,
+    Ranjani,
+    Raktika,
+    Raudri,
+    Krodha,
+    Vajrika,
+    Prasarini,
+    Priti,
+    Marjani,
+    Kshiti,
+    Rakta,
+    Sandipani,
+    Alapini,
+    Madanti,
+    Rohini,
+    Ramya,
+    Ugra,
+}
+
+impl Shruti {
+    pub const ALL: [Shruti; 22] = [
+        Shruti::Kshobhini,
+        Shruti::Tivra,
+        Shruti::Kumudvati,
+        Shruti::Manda,
+        Shruti::Chandovati,
+        Shruti::Dayavati,
+        Shruti::Ranjani,
+        Shruti::Raktika,
+        Shruti::Raudri,
+        Shruti::Krodha,
+        Shruti::Vajrika,
+        Shruti::Prasarini,
+        Shruti::Priti,
+        Shruti::Marjani,
+        Shruti::Kshiti,
+        Shruti::Rakta,
+        Shruti::Sandipani,
+        Shruti::Alapini,
+        Shruti::Madanti,
+        Shruti::Rohini,
+        Shruti::Ramya,
+        Shruti::Ugra,
+    ];
+
+    /// Shruti each swara sits on by default, indexed by swara
+    pub const DEFAULTS: [Shruti; 12] = [
+        Shruti::Kshobhini,
+        Shruti::Kumudvati,
+        Shruti::Chandovati,
+        Shruti::Ranjani,
+        Shruti::Raktika,
+        Shruti::Krodha,
+        Shruti::Prasarini,
+        Shruti::Marjani,
+        Shruti::Rakta,
+        Shruti::Sandipani,
+        Shruti::Madanti,
+        Shruti::Ramya,
+    ];
+
+    /// Position among the 22 shrutis, 0 (Sa) to 21
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn name(self) -> &'static str {
+        [
+            "Kshobhini",
+            "Tivra",
+            "Kumudvati",
+            "Manda",
+            "Chandovati",
+            "Dayavati",
+            "Ranjani",
+            "Raktika",
+            "Raudri",
+            "Krodha",
+            "Vajrika",
+            "Prasarini",
+            "Priti",
+            "Marjani",
+            "Kshiti",
+            "Rakta",
+            "Sandipani",
+            "Alapini",
+            "Madanti",
+            "Rohini",
+            "Ramya",
+            "Ugra",
+        ][self.index()]
+    }
+
+    pub fn ratio(self) -> f32 {
+        let (numerator, denominator) = SHRUTI_RATIOS[self.index()];
+        numerator as f32 / denominator as f32
+    }
+
+    /// Cents above Sa
+    pub fn cents(self) -> f32 {
+        1200.0 * self.ratio().log2()
+    }
+
+    /// The swara this shruti is a variant of, e.g. Dayavati is a low komal Ga
+    pub fn swara(self) -> Swara {
+        Swara::from_index((self.cents() / 100.0).round() as i32)
+    }
+}
+
+impl fmt::Display for Shruti {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Case-insensitive shruti name, e.g. `dayavati`
+impl FromStr for Shruti {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Shruti::ALL
+            .iter()
+            .copied()
+            .find(|shruti| shruti.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("Unknown shruti: {}", s))
+    }
+}
+
+impl Serialize for Shruti {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Shruti {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Pitch of one swara in a raag that departs from the tuning system, given as
+/// a named shruti or in cents above Sa
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Intonation {
+    Shruti(Shruti),
+    Cents(f32),
+}
+
+impl Intonation {
+    pub fn cents(self) -> f32 {
+        match self {
+            Intonation::Shruti(shruti) => shruti.cents(),
+            Intonation::Cents(cents) => cents,
+        }
+    }
+}
+
+/// How the swaras of an octave are tuned relative to Sa
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TuningSystem {
+    /// Twelve equal semitones of 100 cents
+    EqualTemperament,
+    /// Small whole-number ratios to Sa (e.g. 5/4 for Ga, 3/2 for Pa)
+    #[default]
+    JustIntonation,
+    /// Each swara on one of the 22 shrutis, indexed by swara
+    Shruti([Shruti; 12]),
+    /// Cents above Sa of each swara, indexed by swara
+    Custom([f32; 12]),
+}
+
+impl TuningSystem {
+    /// The 22-shruti system with every swara on its default shruti
+    pub fn shruti() -> Self {
+        TuningSystem::Shruti(Shruti::DEFAULTS)
+    }
+
+    /// Cents of `swara` above Sa
+    pub fn cents(&self, swara: Swara) -> f32 {
+        match self {
+            TuningSystem::EqualTemperament => 100.0 * swara.index() as f32,
+            TuningSystem::JustIntonation => {
+                let (numerator, denominator) = JUST_RATIOS[swara.index()];
+                1200.0 * (numerator as f32 / denominator as f32).log2()
+            }
+            TuningSystem::Shruti(shrutis) => shrutis[swara.index()].cents(),
+            TuningSystem::Custom(cents) => cents[swara.index()],
+        }
+    }
+
+    /// Frequency ratio of `swara` to Sa
+    pub fn ratio(&self, swara: Swara) -> f32 {
+        2f32.powf(self.cents(swara) / 1200.0)
+    }
+
+    /// Cents of every swara above Sa, indexed by swara
+    pub fn cent_table(&self) -> [f32; 12] {
+        Swara::ALL.map(|swara| self.cents(swara))
+    }
+
+    /// This tuning with some swaras moved, e.g. Darbari's ati-komal Ga
+    pub fn with_intonation(&self, intonation: &BTreeMap<Swara, Intonation>) -> TuningSystem {
+        if intonation.is_empty() {
+            return *self;
+        }
+        let mut table = self.cent_table();
+        for (swara, pitch) in intonation {
+            table[swara.index()] = pitch.cents();
+        }
+        TuningSystem::Custom(table)
+    }
+
+    /// The swara closest to `cents` above Sa (in any octave), and how many
+    /// cents the pitch lies above it
+    pub fn nearest(&self, cents: f32) -> (Swara, f32) {
+        let folded = cents.rem_euclid(1200.0);
+        Swara::ALL
+            .iter()
+            .map(|&swara| {
+                let deviation = (folded - self.cents(swara) + 600.0).rem_euclid(1200.0) - 600.0;
+                (swara, deviation)
+            })
+            .min_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+            .expect("twelve swaras")
+    }
+}
+
+/// How far `cents` lies from `swara`'s equal-tempered position, if it's close
+/// enough to still count as that swara
+pub fn deviation_from_swara(swara: Swara, cents: f32) -> Option<f32> {
+    let deviation = cents - 100.0 * swara.index() as f32;
+    (cents.is_finite() && deviation.abs() <= MAX_SWARA_DEVIATION_CENTS).then_some(deviation)
+}
+
+/// `et` (or `12-tet`), `just`, `shruti` (or `22-shruti`), or a custom table of
+/// twelve comma-separated cent values starting at Sa
+impl FromStr for TuningSystem {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "et" | "12-tet" | "equal" | "equal-temperament" => return Ok(TuningSystem::EqualTemperament),
+            "just" | "ji" | "just-intonation" => return Ok(TuningSystem::JustIntonation),
+            "shruti" | "22-shruti" => return Ok(TuningSystem::shruti()),
+            _ => {}
+        }
+
+        let values = s
+            .split(',')
+            .map(|value| value.trim().parse::<f32>())
+            .collect::<Result<Vec<f32>, _>>()
+            .map_err(|_| format!("Unknown tuning system: {} (expected et, just, shruti or 12 cent values)", s))?;
+        let table: [f32; 12] = values
+            .try_into()
+            .map_err(|values: Vec<f32>| format!("A custom tuning needs 12 cent values, got {}", values.len()))?;
+        if let Some(swara) = Swara::ALL
+            .into_iter()
+            .find(|&swara| deviation_from_swara(swara, table[swara.index()]).is_none())
+        {
+            return Err(format!(
+                "Custom tuning puts {} at {} cents, too far from {} cents",
+                swara,
+                table[swara.index()],
+                100 * swara.index()
+            ));
+        }
+        Ok(TuningSystem::Custom(table))
+    }
+}
@@ -0,0 +1,212 @@
+mod common;
+
+use raag_detection::audio::{AudioReader, DownmixStrategy, ReadOptions};
+use raag_detection::RaagError;
+use std::io::Cursor;
+
+const SAMPLE_RATE: u32 = 8000;
+
+/// Two seconds of mono audio whose sample `i` is `i / 32768`, so every
+/// decoded sample tells which frame it came from
+fn ramp() -> Vec<i16> {
+    (0..2 * SAMPLE_RATE as i16).collect()
+}
+
+fn frame_numbers(audio: &AudioReader) -> Vec<i32> {
+    audio.samples.iter().map(|s| (s * 32768.0).round() as i32).collect()
+}
+
+/// The ramp as a WAV file, decoded by hound
+fn try_read_wav(options: &ReadOptions) -> raag_detection::Result<AudioReader> {
+    let samples: Vec<f32> = ramp().iter().map(|&s| s as f32 / 32768.0).collect();
+    let path = common::write_wav("ramp", common::pcm16(1, SAMPLE_RATE), &samples);
+    let audio = AudioReader::from_file_with_options(&path, options);
+    std::fs::remove_file(path).ok();
+    audio
+}
+
+/// The ramp as an AIFF stream, decoded by symphonia
+fn try_read_aiff(options: &ReadOptions) -> raag_detection::Result<AudioReader> {
+    let bytes = common::aiff_bytes(1, SAMPLE_RATE, &ramp());
+    AudioReader::from_reader_with_options(Cursor::new(bytes), options)
+}
+
+fn read_wav(options: &ReadOptions) -> AudioReader {
+    try_read_wav(options).unwrap()
+}
+
+fn read_aiff(options: &ReadOptions) -> AudioReader {
+    try_read_aiff(options).unwrap()
+}
+
+#[test]
+fn decodes_the_whole_recording_by_default() {
+    for audio in [read_wav(&ReadOptions::default()), read_aiff(&ReadOptions::default())] {
+        assert_eq!(audio.sample_rate, SAMPLE_RATE);
+        assert_eq!(frame_numbers(&audio), (0..2 * SAMPLE_RATE as i32).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn offset_and_max_duration_select_the_same_frames_on_every_path() {
+    let options = ReadOptions {
+        offset: 0.5,
+        max_duration: Some(1.0),
+    };
+    for audio in [read_wav(&options), read_aiff(&options)] {
+        assert_eq!(frame_numbers(&audio), (4000..12000).collect::<Vec<_>>());
+        assert!((audio.duration_seconds() - 1.0).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn windows_past_the_end_are_clipped() {
+    let options = ReadOptions {
+        offset: 1.5,
+        max_duration: Some(10.0),
+    };
+    for audio in [read_wav(&options), read_aiff(&options)] {
+        assert_eq!(frame_numbers(&audio), (12000..16000).collect::<Vec<_>>());
+    }
+
+    let beyond = ReadOptions {
+        offset: 5.0,
+        max_duration: None,
+    };
+    assert!(matches!(try_read_wav(&beyond), Err(RaagError::NoAudioData)));
+    assert!(matches!(try_read_aiff(&beyond), Err(RaagError::NoAudioData)));
+}
+
+#[test]
+fn offsets_far_into_a_recording_land_on_the_exact_frame() {
+    // Beyond 2^24 frames an f32 can't count every frame; the data is a
+    // sparse file of silence, so nothing that long is written
+    let (rate, frames) = (48_000, 20_000_000);
+    let path = common::temp_path("long", "wav");
+    std::fs::write(&path, common::wav_header(1, rate, frames)).unwrap();
+    std::fs::File::options()
+        .append(true)
+        .open(&path)
+        .unwrap()
+        .set_len(44 + 2 * frames as u64)
+        .unwrap();
+
+    let options = ReadOptions {
+        offset: (19_200_001.0 / rate as f64) as f32,
+        max_duration: Some(0.01),
+    };
+    let audio = AudioReader::from_file_with_options(&path, &options);
+    std::fs::remove_file(path).ok();
+
+    let audio = audio.unwrap();
+    assert_eq!((audio.start_time * rate as f64).round(), 19_200_001.0);
+    assert_eq!(audio.frames(), 480);
+}
+
+/// One second of stereo with a quiet ramp on the left and a loud constant on the right
+fn stereo() -> Vec<i16> {
+    (0..SAMPLE_RATE as i16).flat_map(|i| [i, 16384]).collect()
+}
+
+#[test]
+fn keeps_channels_interleaved_on_every_path() {
+    let samples: Vec<f32> = stereo().iter().map(|&s| s as f32 / 32768.0).collect();
+    let path = common::write_wav("stereo", common::pcm16(2, SAMPLE_RATE), &samples);
+    let wav = AudioReader::from_file(&path).unwrap();
+    std::fs::remove_file(path).ok();
+    let aiff = AudioReader::from_reader(Cursor::new(common::aiff_bytes(2, SAMPLE_RATE, &stereo()))).unwrap();
+
+    for audio in [wav, aiff] {
+        assert_eq!((audio.channels, audio.frames()), (2, SAMPLE_RATE as usize));
+        assert_eq!(&frame_numbers(&audio)[..6], &[0, 16384, 1, 16384, 2, 16384]);
+        assert_eq!(audio.channel(0).unwrap()[100], 100.0 / 32768.0);
+        assert_eq!(audio.channel(1).unwrap()[100], 0.5);
+        assert!(audio.channel(2).is_none());
+    }
+}
+
+#[test]
+fn downmix_strategies_pick_or_mix_channels() {
+    let audio = AudioReader {
+        samples: vec![0.25, -0.5, 0.5, -0.75],
+        sample_rate: SAMPLE_RATE,
+        channels: 2,
+        start_time: 0.0,
+    };
+
+    assert_eq!(audio.to_mono(DownmixStrategy::Average), vec![-0.125, -0.125]);
+    assert_eq!(audio.to_mono(DownmixStrategy::Left), vec![0.25, 0.5]);
+    assert_eq!(audio.to_mono(DownmixStrategy::Right), vec![-0.5, -0.75]);
+    assert_eq!(audio.to_mono(DownmixStrategy::MaxEnergy), vec![-0.5, -0.75]);
+
+    let mono = AudioReader {
+        samples: vec![0.1, 0.3],
+        sample_rate: SAMPLE_RATE,
+        channels: 1,
+        start_time: 0.0,
+    };
+    assert_eq!(mono.to_mono(DownmixStrategy::Right), vec![0.1, 0.3]);
+
+    assert_eq!("max-energy".parse(), Ok(DownmixStrategy::MaxEnergy));
+    assert_eq!("L".parse(), Ok(DownmixStrategy::Left));
+    assert!("centre".parse::<DownmixStrategy>().is_err());
+}
+
+#[test]
+fn reads_wav_from_an_in_memory_buffer() {
+    let samples: Vec<f32> = ramp().iter().map(|&s| s as f32 / 32768.0).collect();
+    let path = common::write_wav("buffer", common::pcm16(1, SAMPLE_RATE), &samples);
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(path).ok();
+
+    let audio = AudioReader::from_reader(Cursor::new(bytes)).unwrap();
+    assert_eq!(frame_numbers(&audio), (0..2 * SAMPLE_RATE as i32).collect::<Vec<_>>());
+}
+
+#[test]
+fn reads_audio_embedded_after_other_bytes_from_where_the_stream_is() {
+    let wav: Vec<u8> = common::wav_header(1, SAMPLE_RATE, ramp().len() as u32)
+        .into_iter()
+        .chain(ramp().iter().flat_map(|s| s.to_le_bytes()))
+        .collect();
+    let aiff = common::aiff_bytes(1, SAMPLE_RATE, &ramp());
+    // Each recording follows one in the other format, which decoding from
+    // byte 0 would pick up instead
+    let decoy = |samples: &[i16]| common::aiff_bytes(1, SAMPLE_RATE, samples);
+    let options = ReadOptions {
+        offset: 0.5,
+        max_duration: Some(1.0),
+    };
+
+    for (leading, recording) in [(decoy(&[7; 100]), &wav), (wav[..44].to_vec(), &aiff)] {
+        let mut stream = Cursor::new([leading.as_slice(), recording].concat());
+        stream.set_position(leading.len() as u64);
+        let audio = AudioReader::from_reader_with_options(stream, &options).unwrap();
+        assert_eq!(frame_numbers(&audio), (4000..12000).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn detects_the_format_from_content_not_the_extension() {
+    let aiff = common::aiff_bytes(1, SAMPLE_RATE, &ramp());
+    let samples: Vec<f32> = ramp().iter().map(|&s| s as f32 / 32768.0).collect();
+    let wav_path = common::write_wav("misnamed", common::pcm16(1, SAMPLE_RATE), &samples);
+    let wav = std::fs::read(&wav_path).unwrap();
+    std::fs::remove_file(wav_path).ok();
+
+    for (bytes, extension) in [(&aiff, "mp3"), (&aiff, ""), (&wav, "flac"), (&wav, "txt")] {
+        let path = common::temp_path("misnamed", extension);
+        std::fs::write(&path, bytes).unwrap();
+        let audio = AudioReader::from_file(&path);
+        std::fs::remove_file(&path).ok();
+
+        let audio = audio.unwrap_or_else(|err| panic!("{} as .{}: {}", path.display(), extension, err));
+        assert_eq!(audio.frames(), 2 * SAMPLE_RATE as usize);
+    }
+}
+
+#[test]
+fn rejects_content_that_is_not_audio() {
+    let result = AudioReader::from_reader(Cursor::new(b"just some text, not a recording".repeat(100)));
+    assert!(matches!(result, Err(RaagError::UnrecognizedFormat)), "{:?}", result.err());
+}
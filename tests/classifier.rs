@@ -0,0 +1,173 @@
+mod common;
+
+use raag_detection::classification::{
+    AudioFeatures, ClassificationResult, DistanceMetric, Raag, RaagClassifier, RaagDatabase, TonicEstimate,
+};
+use raag_detection::features::FeatureStream;
+
+const METRICS: [DistanceMetric; 3] =
+    [DistanceMetric::KlDivergence, DistanceMetric::Bhattacharyya, DistanceMetric::Cosine];
+
+/// Features of a melody that holds each note for 0.3 s
+fn features(notes: &[String]) -> AudioFeatures {
+    let held: Vec<(&str, usize)> = notes.iter().map(|note| (note.as_str(), 30)).collect();
+    let contour = common::melody(&held);
+    AudioFeatures {
+        chromagram: FeatureStream::new(contour.info, Vec::new()),
+        spectral_centroid: FeatureStream::new(contour.info, Vec::new()),
+        pitch_contour: contour,
+        long_term_spectrum: None,
+    }
+}
+
+fn notes(sequence: &str) -> Vec<String> {
+    sequence.split_whitespace().map(str::to_string).collect()
+}
+
+/// A raag's aroha, avaroha and characteristic phrases, one after the other
+fn rendition(raag: &Raag) -> Vec<String> {
+    let phrases = raag.characteristic_phrases.iter().flat_map(|phrase| &phrase.notes);
+    raag.aroha.iter().chain(&raag.avaroha).chain(phrases).map(|note| note.to_string()).collect()
+}
+
+fn classifier(metric: DistanceMetric) -> RaagClassifier {
+    RaagClassifier::new().with_tonic(Some(common::SA_HZ)).with_metric(metric)
+}
+
+#[test]
+fn every_bundled_raag_tops_the_ranking_of_its_own_melody() {
+    let classifier = classifier(DistanceMetric::default());
+    for raag in RaagDatabase::new().get_raags() {
+        let result = classifier.classify(&features(&rendition(raag))).unwrap();
+        assert_eq!(result.raag(), Some(raag.name.as_str()), "{:?}", &result.top(3));
+        assert!(result.candidates[0].score > 0.5, "{}: {:?}", raag.name, &result.top(1));
+    }
+}
+
+#[test]
+fn a_tonic_found_elsewhere_is_used_as_given() {
+    let yaman = features(&rendition(RaagDatabase::new().get_raag("Yaman").unwrap()));
+    let given = TonicEstimate {
+        confidence: 0.8,
+        ..TonicEstimate::user_supplied(common::SA_HZ)
+    };
+
+    let result = RaagClassifier::new().classify_with_tonic(&yaman, given).unwrap();
+    assert_eq!(result.tonic, given);
+    let configured = classifier(DistanceMetric::default()).classify(&yaman).unwrap();
+    let ranking = |result: &ClassificationResult| -> Vec<(String, f32)> {
+        result.candidates.iter().map(|candidate| (candidate.name.clone(), candidate.distance)).collect()
+    };
+    assert_eq!(ranking(&result), ranking(&configured));
+}
+
+#[test]
+fn temperatures_give_a_clear_but_not_saturated_preference() {
+    // Sister raags sharing most notes should keep some of the score, while
+    // the right raag stays well ahead of an even share of the catalogue
+    let database = RaagDatabase::new();
+    for metric in METRICS {
+        let classifier = classifier(metric);
+        for raag in database.get_raags() {
+            let result = classifier.classify(&features(&rendition(raag))).unwrap();
+            let top = result.top(2);
+            assert!(top.iter().any(|candidate| candidate.name == raag.name), "{:?} {}: {:?}", metric, raag.name, top);
+            assert!(top[0].share > 0.2 && top[0].share < 0.9, "{:?} {}: {:?}", metric, raag.name, top);
+        }
+    }
+}
+
+#[test]
+fn shares_are_relative_to_the_catalogue_but_scores_are_not() {
+    let full = RaagDatabase::new();
+    let yaman = full.get_raag("Yaman").unwrap().clone();
+    let few: Vec<Raag> = ["Yaman", "Bhairavi", "Todi"]
+        .iter()
+        .map(|&name| full.get_raag(name).unwrap().clone())
+        .collect();
+    let melody = features(&rendition(&yaman));
+
+    let in_full = classifier(DistanceMetric::default()).classify(&melody).unwrap();
+    let in_few = classifier(DistanceMetric::default())
+        .with_database(RaagDatabase::from_raags(few))
+        .classify(&melody)
+        .unwrap();
+
+    assert_eq!((in_full.raag(), in_few.raag()), (Some("Yaman"), Some("Yaman")));
+    let (full_best, few_best) = (&in_full.candidates[0], &in_few.candidates[0]);
+    assert!(few_best.share > full_best.share + 0.2);
+    assert_eq!((few_best.score, few_best.distance), (full_best.score, full_best.distance));
+    for result in [&in_full, &in_few] {
+        assert!((result.candidates.iter().map(|candidate| candidate.share).sum::<f32>() - 1.0).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn abstains_when_no_raag_fits() {
+    let chromatic = features(&notes("S r R g G m M P d D n N S' N n D d P M m G g R r S"));
+    for metric in METRICS {
+        let result = classifier(metric).classify(&chromatic).unwrap();
+        assert!(result.abstained, "{:?}: {:?}", metric, &result.top(1));
+        assert_eq!((result.raag(), result.candidates[0].score), (None, 0.0));
+        assert_eq!(result.candidates.len(), RaagDatabase::new().get_raags().len());
+    }
+}
+
+#[test]
+fn the_abstain_rule_looks_at_the_distance_not_the_score() {
+    let chromatic = features(&notes("S r R g G m M P d D n N S' N n D d P M m G g R r S"));
+    let lenient = classifier(DistanceMetric::default()).with_max_distance(Some(1.0));
+    assert!(lenient.classify(&chromatic).unwrap().raag().is_some());
+
+    // A single raag always takes the whole share, yet still abstains, with no
+    // confidence, when it fits badly
+    let database = RaagDatabase::new();
+    let only_bhairavi = RaagDatabase::from_raags(vec![database.get_raag("Bhairavi").unwrap().clone()]);
+    let yaman = features(&rendition(database.get_raag("Yaman").unwrap()));
+    let result = classifier(DistanceMetric::default()).with_database(only_bhairavi).classify(&yaman).unwrap();
+    assert_eq!((result.candidates[0].share, result.candidates[0].score), (1.0, 0.0));
+    assert!(result.abstained);
+}
+
+/// Two raags on the same notes, the first listing a phrase and the second none
+fn twins() -> RaagDatabase {
+    let json = r#"{ "raags": [
+        { "name": "With phrase", "thaat": "Kalyan", "aroha": "S R G P D S'", "avaroha": "S' D P G R S",
+          "vadi": "G", "samvadi": "D", "characteristic_phrases": [{ "notes": "G D S' P", "weight": 1.0 }] },
+        { "name": "Without phrase", "thaat": "Kalyan", "aroha": "S R G P D S'", "avaroha": "S' D P G R S",
+          "vadi": "G", "samvadi": "D" }
+    ] }"#;
+    RaagDatabase::from_json_str(json).unwrap()
+}
+
+fn share_of(result: &ClassificationResult, name: &str) -> f32 {
+    result.candidates.iter().find(|candidate| candidate.name == name).unwrap().share
+}
+
+#[test]
+fn listed_phrases_count_for_a_raag_when_heard_and_against_it_when_missing() {
+    let classifier = classifier(DistanceMetric::default()).with_database(twins());
+    let scale = "S R G P D S' D P G R S";
+
+    let without = classifier.classify(&features(&notes(scale))).unwrap();
+    assert!(without.candidates[0].evidence.phrase_matches.is_empty());
+    assert_eq!(without.raag(), Some("Without phrase"));
+
+    let with = classifier.classify(&features(&notes(&format!("{} G D S' P", scale)))).unwrap();
+    assert_eq!(with.raag(), Some("With phrase"));
+    assert!(share_of(&with, "With phrase") > share_of(&without, "With phrase"));
+}
+
+#[test]
+fn phrases_matter_less_at_a_lower_weight() {
+    let melody = features(&notes("S R G P D S' D P G R S"));
+    let gap = |weight: f32| {
+        let result = classifier(DistanceMetric::default())
+            .with_database(twins())
+            .with_phrase_weight(weight)
+            .classify(&melody)
+            .unwrap();
+        share_of(&result, "Without phrase") - share_of(&result, "With phrase")
+    };
+    assert!(gap(2.0) > gap(1.0) && gap(1.0) > gap(0.0));
+}
@@ -0,0 +1,149 @@
+//! Fixtures shared by the integration tests
+#![allow(dead_code)]
+
+use hound::{SampleFormat, WavSpec, WavWriter};
+use raag_detection::features::{FeatureStream, FrameConfig, PitchFrame, StreamInfo};
+use raag_detection::theory::{Note, TuningSystem};
+use std::f32::consts::PI;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A path in the temp directory that no other test, in this process or
+/// another, uses; an empty `extension` leaves the file without one
+pub fn temp_path(name: &str, extension: &str) -> PathBuf {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let count = COUNT.fetch_add(1, Ordering::Relaxed);
+    let path = std::env::temp_dir().join(format!("raag-detection-{}-{}-{}", std::process::id(), count, name));
+    path.with_extension(extension)
+}
+
+/// Write interleaved samples in [-1, 1] to a WAV file in the given format
+pub fn write_wav(name: &str, spec: WavSpec, samples: &[f32]) -> PathBuf {
+    let path = temp_path(name, "wav");
+    let mut writer = WavWriter::create(&path, spec).unwrap();
+    let full_scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+
+    for &value in samples {
+        match spec.sample_format {
+            SampleFormat::Float => writer.write_sample(value).unwrap(),
+            SampleFormat::Int => writer.write_sample((value * full_scale).round() as i32).unwrap(),
+        }
+    }
+    writer.finalize().unwrap();
+
+    path
+}
+
+/// 16-bit integer WAV spec
+pub fn pcm16(channels: u16, sample_rate: u32) -> WavSpec {
+    WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    }
+}
+
+/// Header of a 16-bit PCM WAV file whose data chunk holds `frames` frames
+pub fn wav_header(channels: u16, sample_rate: u32, frames: u32) -> Vec<u8> {
+    let block_align = 2 * channels;
+    let data_len = frames * block_align as u32;
+
+    let mut bytes = b"RIFF".to_vec();
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&channels.to_le_bytes());
+    bytes.extend_from_slice(&sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+    bytes.extend_from_slice(&block_align.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    bytes
+}
+
+/// A 16-bit AIFF file of interleaved samples, which only symphonia decodes
+pub fn aiff_bytes(channels: u16, sample_rate: u32, samples: &[i16]) -> Vec<u8> {
+    let frames = (samples.len() / channels as usize) as u32;
+
+    let mut common = Vec::new();
+    common.extend_from_slice(&channels.to_be_bytes());
+    common.extend_from_slice(&frames.to_be_bytes());
+    common.extend_from_slice(&16u16.to_be_bytes());
+    // The sample rate is an 80-bit extended float: exponent, then a mantissa
+    // with an explicit leading one
+    let exponent = 31 - sample_rate.leading_zeros();
+    common.extend_from_slice(&(16383 + exponent as u16).to_be_bytes());
+    common.extend_from_slice(&((sample_rate as u64) << (63 - exponent)).to_be_bytes());
+
+    let mut sound = vec![0u8; 8];
+    for sample in samples {
+        sound.extend_from_slice(&sample.to_be_bytes());
+    }
+
+    let mut form = b"AIFF".to_vec();
+    for (tag, chunk) in [(b"COMM", common), (b"SSND", sound)] {
+        form.extend_from_slice(tag);
+        form.extend_from_slice(&(chunk.len() as u32).to_be_bytes());
+        form.extend_from_slice(&chunk);
+    }
+
+    let mut bytes = b"FORM".to_vec();
+    bytes.extend_from_slice(&(form.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(&form);
+    bytes
+}
+
+/// `seconds` of a full-scale sine at `frequency`
+pub fn sine(frequency: f32, sample_rate: u32, seconds: f32) -> Vec<f32> {
+    (0..(seconds * sample_rate as f32) as usize)
+        .map(|i| (2.0 * PI * frequency * i as f32 / sample_rate as f32).sin())
+        .collect()
+}
+
+pub fn rms(samples: &[f32]) -> f32 {
+    (samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+/// Sa of the synthetic pitch contours
+pub const SA_HZ: f32 = 200.0;
+
+/// Pitch contour at 100 frames per second, with frame `i` centred at `i /
+/// 100` seconds; frequencies of zero are unvoiced
+pub fn pitch_stream(frequencies: Vec<f32>) -> FeatureStream<PitchFrame> {
+    let frames = frequencies
+        .into_iter()
+        .map(|frequency| PitchFrame {
+            frequency,
+            voicing: if frequency > 0.0 { 1.0 } else { 0.0 },
+        })
+        .collect();
+    FeatureStream::new(StreamInfo::new(1000, FrameConfig::new(0, 10), 0.0), frames)
+}
+
+/// Cents of `note` above Sa under the default tuning
+pub fn cents_of(note: &str) -> f32 {
+    note.parse::<Note>().unwrap().cents(&TuningSystem::default())
+}
+
+/// Frequency `cents` above Sa
+pub fn frequency(cents: f32) -> f32 {
+    SA_HZ * 2f32.powf(cents / 1200.0)
+}
+
+/// Frequencies of `note` held steady for `frames` frames
+pub fn hold(note: &str, frames: usize) -> Vec<f32> {
+    hold_off(note, frames, 0.0)
+}
+
+/// Like `hold`, sung `cents` off the swara
+pub fn hold_off(note: &str, frames: usize, cents: f32) -> Vec<f32> {
+    vec![frequency(cents_of(note) + cents); frames]
+}
+
+/// Contour of a melody given as `(note, frames held)` pairs
+pub fn melody(notes: &[(&str, usize)]) -> FeatureStream<PitchFrame> {
+    pitch_stream(notes.iter().flat_map(|&(note, frames)| hold(note, frames)).collect())
+}
@@ -0,0 +1,174 @@
+use raag_detection::audio::{AudioReader, ReadOptions};
+use raag_detection::RaagError;
+use std::collections::VecDeque;
+use std::io::Cursor;
+use symphonia::core::audio::Channels;
+use symphonia::core::codecs::{CodecParameters, CODEC_TYPE_PCM_S16LE};
+use symphonia::core::errors::{Error as SymphoniaError, Result as SymphoniaResult};
+use symphonia::core::formats::{Cue, FormatOptions, FormatReader, Packet, SeekMode, SeekTo, SeekedTo, Track};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::{Metadata, MetadataLog};
+
+/// IMA ADPCM blocks of this many bytes hold 2041 frames, more than symphonia
+/// puts in one packet, so every block is a packet of its own
+const BLOCK_ALIGN: usize = 1024;
+const FRAMES_PER_BLOCK: usize = (BLOCK_ALIGN - 4) * 2 + 1;
+
+/// A mono IMA ADPCM WAV, which hound can't read, with one block per entry of
+/// `predictors`. All-zero nibbles hold each block at its predictor; `None`
+/// gives the block an invalid step index, so it fails to decode.
+fn adpcm_wav(predictors: &[Option<i16>]) -> Vec<u8> {
+    let mut format = Vec::new();
+    format.extend_from_slice(&0x0011u16.to_le_bytes());
+    format.extend_from_slice(&1u16.to_le_bytes());
+    format.extend_from_slice(&8000u32.to_le_bytes());
+    format.extend_from_slice(&((8000 * BLOCK_ALIGN / FRAMES_PER_BLOCK) as u32).to_le_bytes());
+    format.extend_from_slice(&(BLOCK_ALIGN as u16).to_le_bytes());
+    format.extend_from_slice(&4u16.to_le_bytes());
+    format.extend_from_slice(&2u16.to_le_bytes());
+    format.extend_from_slice(&(FRAMES_PER_BLOCK as u16).to_le_bytes());
+
+    let mut data = Vec::new();
+    for predictor in predictors {
+        let mut block = vec![0u8; BLOCK_ALIGN];
+        block[..2].copy_from_slice(&predictor.unwrap_or(0).to_le_bytes());
+        block[2] = if predictor.is_some() { 0 } else { 200 };
+        data.extend_from_slice(&block);
+    }
+
+    let mut wave = b"WAVE".to_vec();
+    for (tag, chunk) in [(b"fmt ", format), (b"data", data)] {
+        wave.extend_from_slice(tag);
+        wave.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+        wave.extend_from_slice(&chunk);
+    }
+    let mut bytes = b"RIFF".to_vec();
+    bytes.extend_from_slice(&(wave.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&wave);
+    bytes
+}
+
+#[test]
+fn skips_corrupt_packets_and_keeps_the_rest() {
+    let blocks = [Some(1000), Some(2000), None, Some(4000), Some(5000)];
+    let audio = AudioReader::from_reader(Cursor::new(adpcm_wav(&blocks))).unwrap();
+
+    let expected: Vec<f32> = [1000, 2000, 4000, 5000]
+        .iter()
+        .flat_map(|&level| vec![level as f32 / 32768.0; FRAMES_PER_BLOCK])
+        .collect();
+    assert_eq!(audio.samples, expected);
+}
+
+#[test]
+fn gives_up_on_a_stream_of_corrupt_packets() {
+    let blocks = vec![None; 120];
+    let result = AudioReader::from_reader(Cursor::new(adpcm_wav(&blocks)));
+    assert!(matches!(result, Err(RaagError::CorruptStream(100))));
+}
+
+/// What the scripted demuxer does on each call to `next_packet`
+enum Step {
+    Packet { track: u32, samples: Vec<i16> },
+    /// Replace the track list and ask for a reset, as a chained OGG stream does
+    Reset(Vec<Track>),
+}
+
+struct ScriptedDemuxer {
+    tracks: Vec<Track>,
+    steps: VecDeque<Step>,
+    metadata: MetadataLog,
+}
+
+fn pcm_track(id: u32) -> Track {
+    let mut params = CodecParameters::new();
+    params
+        .for_codec(CODEC_TYPE_PCM_S16LE)
+        .with_sample_rate(8000)
+        .with_channels(Channels::FRONT_LEFT)
+        .with_bits_per_coded_sample(16)
+        .with_max_frames_per_packet(64);
+    Track::new(id, params)
+}
+
+impl FormatReader for ScriptedDemuxer {
+    fn try_new(_: MediaSourceStream, _: &FormatOptions) -> SymphoniaResult<Self> {
+        unimplemented!("built directly by the tests")
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &[]
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn seek(&mut self, _: SeekMode, _: SeekTo) -> SymphoniaResult<SeekedTo> {
+        Err(SymphoniaError::Unsupported("seeking"))
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn next_packet(&mut self) -> SymphoniaResult<Packet> {
+        match self.steps.pop_front() {
+            Some(Step::Packet { track, samples }) => {
+                let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+                Ok(Packet::new_from_slice(track, 0, samples.len() as u64, &bytes))
+            }
+            Some(Step::Reset(tracks)) => {
+                self.tracks = tracks;
+                Err(SymphoniaError::ResetRequired)
+            }
+            None => Err(SymphoniaError::IoError(std::io::ErrorKind::UnexpectedEof.into())),
+        }
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        MediaSourceStream::new(Box::new(Cursor::new(Vec::new())), Default::default())
+    }
+}
+
+fn decode(tracks: Vec<Track>, steps: Vec<Step>) -> AudioReader {
+    let demuxer = ScriptedDemuxer {
+        tracks,
+        steps: steps.into(),
+        metadata: MetadataLog::default(),
+    };
+    AudioReader::from_format_reader(Box::new(demuxer), &ReadOptions::default()).unwrap()
+}
+
+fn levels(audio: &AudioReader) -> Vec<i16> {
+    audio.samples.iter().map(|s| (s * 32768.0).round() as i16).collect()
+}
+
+#[test]
+fn follows_a_new_logical_stream_after_a_reset() {
+    let audio = decode(
+        vec![pcm_track(1)],
+        vec![
+            Step::Packet { track: 1, samples: vec![1, 2] },
+            Step::Reset(vec![pcm_track(2)]),
+            Step::Packet { track: 1, samples: vec![99] },
+            Step::Packet { track: 2, samples: vec![3, 4] },
+        ],
+    );
+    assert_eq!(levels(&audio), vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn keeps_decoding_the_same_track_when_it_survives_a_reset() {
+    let audio = decode(
+        vec![pcm_track(1), pcm_track(2)],
+        vec![
+            Step::Packet { track: 2, samples: vec![1] },
+            Step::Packet { track: 1, samples: vec![5] },
+            Step::Reset(vec![pcm_track(2), pcm_track(1)]),
+            Step::Packet { track: 2, samples: vec![9] },
+            Step::Packet { track: 1, samples: vec![6] },
+        ],
+    );
+    assert_eq!(levels(&audio), vec![5, 6]);
+}
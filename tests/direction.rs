@@ -0,0 +1,107 @@
+mod common;
+
+use common::SA_HZ;
+use raag_detection::classification::direction::movements;
+use raag_detection::classification::{
+    Direction, DirectionalProfile, DistanceMetric, Note, Raag, RaagDatabase, TranscribedNote, Transcriber,
+};
+use raag_detection::theory::TuningSystem;
+
+/// Frames each note of a synthetic melody is held for (at 100 frames per second)
+const FRAMES_PER_NOTE: usize = 10;
+
+/// Transcription of a melody that holds each note in turn
+fn transcribe(melody: &str) -> Vec<TranscribedNote> {
+    transcribe_sung_in(melody, &TuningSystem::default())
+}
+
+/// Like `transcribe`, with the notes sung at their pitches under `sung_in`
+fn transcribe_sung_in(melody: &str, sung_in: &TuningSystem) -> Vec<TranscribedNote> {
+    let tuning = TuningSystem::default();
+    let frequencies = Note::parse_sequence(melody)
+        .unwrap()
+        .iter()
+        .flat_map(|note| std::iter::repeat_n(SA_HZ * note.ratio(sung_in), FRAMES_PER_NOTE))
+        .collect();
+    let contour = common::pitch_stream(frequencies);
+    Transcriber::default().transcribe(&contour, SA_HZ, &tuning).notes
+}
+
+fn profile(melody: &str) -> DirectionalProfile {
+    DirectionalProfile::from_movements(&movements(&transcribe(melody)), 12)
+}
+
+/// Mean of the aroha and avaroha distances between a melody and a raag
+fn distance(melody: &str, raag: &str) -> f32 {
+    let database = RaagDatabase::new();
+    let template = DirectionalProfile::from_raag(database.get_raag(raag).unwrap(), 12, &TuningSystem::default());
+    let (aroha, avaroha) = profile(melody).distances(&template, DistanceMetric::Bhattacharyya);
+    (aroha.unwrap() + avaroha.unwrap()) / 2.0
+}
+
+fn steps(notes: &[TranscribedNote]) -> Vec<i32> {
+    notes.iter().map(|transcribed| transcribed.note.semitones()).collect()
+}
+
+#[test]
+fn short_notes_are_ignored() {
+    let blip = [common::hold("S", FRAMES_PER_NOTE), common::hold("R", 2), common::hold("G", FRAMES_PER_NOTE)];
+    let contour = common::pitch_stream(blip.concat());
+    let transcription = Transcriber::default().transcribe(&contour, SA_HZ, &TuningSystem::default());
+    assert_eq!(steps(&transcription.notes), [0, 4]);
+}
+
+#[test]
+fn melody_splits_at_turning_points() {
+    let runs: Vec<(Direction, Vec<i32>)> = movements(&transcribe("S R G R S"))
+        .into_iter()
+        .map(|movement| (movement.direction, steps(&movement.notes)))
+        .collect();
+    assert_eq!(runs, [(Direction::Ascending, vec![0, 2, 4]), (Direction::Descending, vec![4, 2, 0])]);
+}
+
+#[test]
+fn movements_keep_the_octave() {
+    let runs: Vec<Vec<i32>> = movements(&transcribe(".N S R S'"))
+        .into_iter()
+        .map(|movement| steps(&movement.notes))
+        .collect();
+    assert_eq!(runs, [vec![-1, 0, 2, 12]]);
+}
+
+#[test]
+fn khamaj_movement_is_closer_to_khamaj_than_desh() {
+    // Same notes overall; Khamaj takes G and D going up, Desh skips them
+    let melody = "S G m P D N S' S' n D P m G R S";
+    assert!(distance(melody, "Khamaj") < distance(melody, "Desh"));
+}
+
+#[test]
+fn bhimpalasi_movement_is_closer_to_bhimpalasi_than_kafi() {
+    // Same notes overall; Bhimpalasi omits Re and Dha in the aroha
+    let melody = ".n S g m P n S' S' n D P m g R S";
+    assert!(distance(melody, "Bhimpalasi") < distance(melody, "Kafi"));
+}
+
+#[test]
+fn intonation_counts_on_both_sides_at_fine_resolution() {
+    // Darbari's ati-komal Ga and Dha sit 20 cents below the default tuning's,
+    // two bins away at 120 bins per octave
+    let database = RaagDatabase::new();
+    let darbari = database.get_raag("Darbari").unwrap();
+    let mut untuned = darbari.clone();
+    untuned.intonation.clear();
+
+    let base = TuningSystem::default();
+    let melody = "S R g m P d n S' n d P m g R S";
+    let distance = |sung_in: &TuningSystem, raag: &Raag| {
+        let profile = DirectionalProfile::from_movements(&movements(&transcribe_sung_in(melody, sung_in)), 120);
+        let (aroha, avaroha) =
+            profile.distances(&DirectionalProfile::from_raag(raag, 120, &base), DistanceMetric::Bhattacharyya);
+        aroha.unwrap() + avaroha.unwrap()
+    };
+
+    let darbari_tuning = darbari.tuning(&base);
+    assert!(distance(&darbari_tuning, darbari) < distance(&darbari_tuning, &untuned));
+    assert!(distance(&base, &untuned) < distance(&base, darbari));
+}
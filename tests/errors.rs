@@ -0,0 +1,37 @@
+use raag_detection::RaagError;
+use std::io::ErrorKind;
+use symphonia::core::errors::{Error as SymphoniaError, SeekErrorKind};
+
+#[test]
+fn symphonia_failures_keep_their_class() {
+    let io = RaagError::from(SymphoniaError::IoError(ErrorKind::PermissionDenied.into()));
+    assert!(matches!(io, RaagError::Io(err) if err.kind() == ErrorKind::PermissionDenied));
+    assert!(matches!(
+        RaagError::from(SymphoniaError::Unsupported("core (codec): unsupported codec")),
+        RaagError::UnsupportedCodec(_)
+    ));
+    assert!(matches!(
+        RaagError::from(SymphoniaError::DecodeError("bad frame")),
+        RaagError::MalformedAudio(_)
+    ));
+    assert!(matches!(RaagError::from(SymphoniaError::ResetRequired), RaagError::DecoderReset));
+    assert!(matches!(
+        RaagError::from(SymphoniaError::SeekError(SeekErrorKind::OutOfRange)),
+        RaagError::SeekFailed(_)
+    ));
+    assert!(matches!(
+        RaagError::from(SymphoniaError::LimitError("too many channels")),
+        RaagError::LimitExceeded(_)
+    ));
+}
+
+#[test]
+fn hound_failures_keep_their_class() {
+    let io = RaagError::from(hound::Error::IoError(ErrorKind::NotFound.into()));
+    assert!(matches!(io, RaagError::Io(err) if err.kind() == ErrorKind::NotFound));
+    assert!(matches!(RaagError::from(hound::Error::Unsupported), RaagError::UnsupportedCodec(_)));
+    assert!(matches!(
+        RaagError::from(hound::Error::FormatError("no fmt chunk")),
+        RaagError::MalformedAudio(_)
+    ));
+}
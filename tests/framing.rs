@@ -0,0 +1,62 @@
+use raag_detection::features::{FrameConfig, FramePadding};
+
+/// Samples 1, 2, ..., 10, so zeros can only come from padding
+fn signal() -> Vec<f32> {
+    (1..=10).map(|i| i as f32).collect()
+}
+
+fn frames(config: FrameConfig) -> Vec<Vec<f32>> {
+    config.frames(&signal()).map(|frame| frame.into_owned()).collect()
+}
+
+#[test]
+fn centred_frames_are_padded_on_both_sides() {
+    let config = FrameConfig::new(4, 3);
+    assert_eq!(config.padding, FramePadding::Center);
+
+    assert_eq!(config.frame_count(10), 4);
+    assert_eq!(
+        frames(config),
+        vec![
+            vec![0.0, 0.0, 1.0, 2.0],
+            vec![2.0, 3.0, 4.0, 5.0],
+            vec![5.0, 6.0, 7.0, 8.0],
+            vec![8.0, 9.0, 10.0, 0.0],
+        ]
+    );
+    // Frame i is centred on sample i * hop
+    assert_eq!(config.frame_times(4, 10), vec![0.0, 0.3, 0.6, 0.9]);
+}
+
+#[test]
+fn end_padded_frames_start_on_each_hop() {
+    let config = FrameConfig::new(4, 3).with_padding(FramePadding::End);
+
+    assert_eq!(config.frame_count(10), 4);
+    assert_eq!(frames(config)[0], vec![1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(frames(config)[3], vec![10.0, 0.0, 0.0, 0.0]);
+    assert_eq!(config.frame_times(4, 10), vec![0.2, 0.5, 0.8, 1.1]);
+}
+
+#[test]
+fn unpadded_frames_stay_inside_the_signal() {
+    let config = FrameConfig::new(4, 3).with_padding(FramePadding::None);
+
+    assert_eq!(config.frame_count(10), 3);
+    assert_eq!(config.frame_count(3), 0);
+    assert_eq!(frames(config)[2], vec![7.0, 8.0, 9.0, 10.0]);
+    assert_eq!(config.frame_times(3, 10), vec![0.2, 0.5, 0.8]);
+}
+
+#[test]
+fn every_padding_gives_full_length_frames_and_none_for_empty_input() {
+    for padding in [FramePadding::Center, FramePadding::End, FramePadding::None] {
+        let config = FrameConfig::new(2048, 512).with_padding(padding);
+        let samples = vec![0.5; 22050];
+        assert_eq!(config.frames(&samples).len(), config.frame_count(samples.len()));
+        assert!(config.frames(&samples).all(|frame| frame.len() == 2048), "{:?}", padding);
+        assert_eq!(config.frame_count(0), 0);
+    }
+    assert_eq!(FrameConfig::new(2048, 512).frame_count(22050), 44);
+    assert_eq!(FrameConfig::new(2048, 0).hop_size, 1);
+}
@@ -0,0 +1,176 @@
+use raag_detection::classification::RaagDatabase;
+use raag_detection::theory::{Notation, Note, Octave, Swara};
+
+fn sargam(text: &str) -> Note {
+    Note::parse(text, Notation::Sargam).unwrap_or_else(|err| panic!("{}", err))
+}
+
+fn bhatkhande(text: &str) -> Note {
+    Note::parse(text, Notation::Bhatkhande).unwrap_or_else(|err| panic!("{}", err))
+}
+
+fn western(text: &str) -> Note {
+    Note::parse(text, Notation::Western).unwrap_or_else(|err| panic!("{}", err))
+}
+
+fn madhya(swara: Swara) -> Note {
+    Note::new(swara, Octave::Madhya)
+}
+
+#[test]
+fn sargam_letters_are_case_sensitive() {
+    let letters = ["S", "r", "R", "g", "G", "m", "M", "P", "d", "D", "n", "N"];
+    for (letter, swara) in letters.iter().zip(Swara::ALL) {
+        assert_eq!(sargam(letter), madhya(swara), "{}", letter);
+    }
+    assert_eq!(sargam("m^"), madhya(Swara::TivraMa));
+    assert!(Note::parse("s", Notation::Sargam).is_err());
+    assert!(Note::parse("p", Notation::Sargam).is_err());
+}
+
+#[test]
+fn sargam_octave_marks() {
+    assert_eq!(sargam(".N"), Note::new(Swara::Ni, Octave::Mandra));
+    assert_eq!(sargam("N."), Note::new(Swara::Ni, Octave::Mandra));
+    assert_eq!(sargam("d,"), Note::new(Swara::KomalDha, Octave::Mandra));
+    for taar in ["S'", "S’", "S*"] {
+        assert_eq!(sargam(taar), Note::new(Swara::Sa, Octave::Taar), "{}", taar);
+    }
+    assert_eq!(sargam("r'"), Note::new(Swara::KomalRe, Octave::Taar));
+}
+
+#[test]
+fn sargam_names_with_komal_and_tivra_marks() {
+    let cases = [
+        ("Sa", Swara::Sa),
+        ("ri", Swara::Re),
+        ("komal Re", Swara::KomalRe),
+        ("Re(k)", Swara::KomalRe),
+        ("Gab", Swara::KomalGa),
+        ("shuddha Ga", Swara::Ga),
+        ("tivra Ma", Swara::TivraMa),
+        ("Teevra ma", Swara::TivraMa),
+        ("Ma#", Swara::TivraMa),
+        ("Ma(t)", Swara::TivraMa),
+        ("Dha♭", Swara::KomalDha),
+        ("da", Swara::Dha),
+        ("Ni♭", Swara::KomalNi),
+        ("NI", Swara::Ni),
+    ];
+    for (text, swara) in cases {
+        assert_eq!(sargam(text), madhya(swara), "{}", text);
+    }
+    assert_eq!(sargam(".komal Dha"), Note::new(Swara::KomalDha, Octave::Mandra));
+    assert_eq!(sargam("Sa'"), Note::new(Swara::Sa, Octave::Taar));
+}
+
+#[test]
+fn sargam_rejects_alterations_a_swara_does_not_have() {
+    for text in ["komal Sa", "tivra Re", "Pa#", "Ma(k)", "X", "Sam", "", "..S", "S''"] {
+        assert!(Note::parse(text, Notation::Sargam).is_err(), "{}", text);
+    }
+}
+
+#[test]
+fn bhatkhande_swaras_and_marks() {
+    let cases = [
+        ("सा", Swara::Sa),
+        ("स", Swara::Sa),
+        ("रे", Swara::Re),
+        ("रे\u{0332}", Swara::KomalRe),
+        ("ग_", Swara::KomalGa),
+        ("ग", Swara::Ga),
+        ("म", Swara::Ma),
+        ("म\u{030D}", Swara::TivraMa),
+        ("म\u{0951}", Swara::TivraMa),
+        ("प", Swara::Pa),
+        ("ध\u{0332}", Swara::KomalDha),
+        ("नि\u{0332}", Swara::KomalNi),
+        ("नि", Swara::Ni),
+    ];
+    for (text, swara) in cases {
+        assert_eq!(bhatkhande(text), madhya(swara), "{}", text);
+    }
+
+    assert_eq!(bhatkhande("नि\u{0323}"), Note::new(Swara::Ni, Octave::Mandra));
+    assert_eq!(bhatkhande("ध\u{093C}"), Note::new(Swara::Dha, Octave::Mandra));
+    assert_eq!(bhatkhande("सां"), Note::new(Swara::Sa, Octave::Taar));
+    assert_eq!(bhatkhande("रे\u{0307}"), Note::new(Swara::Re, Octave::Taar));
+    assert_eq!(bhatkhande("रे\u{0332}\u{0307}"), Note::new(Swara::KomalRe, Octave::Taar));
+}
+
+#[test]
+fn bhatkhande_rejects_unknown_letters_and_alterations() {
+    for text in ["क", "सा\u{0332}", "प\u{030D}", "ग\u{030D}", "S", "सा\u{0307}\u{0307}"] {
+        assert!(Note::parse(text, Notation::Bhatkhande).is_err(), "{}", text);
+    }
+}
+
+#[test]
+fn western_names_put_sa_on_c() {
+    let names = ["C", "Db", "D", "Eb", "E", "F", "F#", "G", "Ab", "A", "Bb", "B"];
+    for (name, swara) in names.iter().zip(Swara::ALL) {
+        assert_eq!(western(name), madhya(swara), "{}", name);
+    }
+    assert_eq!(western("C#"), madhya(Swara::KomalRe));
+    assert_eq!(western("e♭"), madhya(Swara::KomalGa));
+    assert_eq!(western("G♯"), madhya(Swara::KomalDha));
+}
+
+#[test]
+fn western_octave_numbers_select_the_saptak() {
+    assert_eq!(western("B3"), Note::new(Swara::Ni, Octave::Mandra));
+    assert_eq!(western("C4"), Note::new(Swara::Sa, Octave::Madhya));
+    assert_eq!(western("C5"), Note::new(Swara::Sa, Octave::Taar));
+    assert_eq!(western("Bb5"), Note::new(Swara::KomalNi, Octave::Taar));
+    // Cb4 is a semitone below madhya Sa
+    assert_eq!(western("Cb4"), Note::new(Swara::Ni, Octave::Mandra));
+
+    for text in ["C6", "B2", "H", "Cx", "C#x", ""] {
+        assert!(Note::parse(text, Notation::Western).is_err(), "{}", text);
+    }
+}
+
+#[test]
+fn from_str_reads_sargam_and_bhatkhande_but_not_western() {
+    assert_eq!("komal Re".parse::<Note>(), Ok(madhya(Swara::KomalRe)));
+    assert_eq!("रे\u{0332}".parse::<Note>(), Ok(madhya(Swara::KomalRe)));
+    // In sargam D is shuddha Dha; as a Western name it would be Re
+    assert_eq!("D".parse::<Note>(), Ok(madhya(Swara::Dha)));
+    assert!("C#".parse::<Note>().is_err());
+    assert!("Db".parse::<Note>().is_err());
+
+    assert_eq!("g".parse::<Swara>(), Ok(Swara::KomalGa));
+    assert_eq!("tivra Ma".parse::<Swara>(), Ok(Swara::TivraMa));
+}
+
+#[test]
+fn every_note_survives_a_round_trip_through_its_display() {
+    for octave in [Octave::Mandra, Octave::Madhya, Octave::Taar] {
+        for swara in Swara::ALL {
+            let note = Note::new(swara, octave);
+            assert_eq!(note.to_string().parse::<Note>(), Ok(note));
+        }
+    }
+}
+
+#[test]
+fn sequences_mix_notations_and_report_the_bad_note() {
+    let notes = Note::parse_sequence(".N S  रे ग M P").unwrap();
+    let swaras: Vec<Swara> = notes.iter().map(|note| note.swara).collect();
+    assert_eq!(swaras, [Swara::Ni, Swara::Sa, Swara::Re, Swara::Ga, Swara::TivraMa, Swara::Pa]);
+    assert_eq!(notes[0].octave, Octave::Mandra);
+
+    let error = Note::parse_sequence("S R Q G").unwrap_err();
+    assert!(error.contains('Q'), "{}", error);
+}
+
+#[test]
+fn definitions_may_be_written_in_bhatkhande() {
+    let json = r#"{ "raags": [{ "name": "Devanagari Bhupali", "thaat": "Kalyan",
+        "aroha": "सा रे ग प ध सां", "avaroha": "सां ध प ग रे सा", "vadi": "ग", "samvadi": "ध" }] }"#;
+    let database = RaagDatabase::from_json_str(json).unwrap();
+    let bhupali = &database.get_raags()[0];
+    assert_eq!(bhupali.aroha, Note::parse_sequence("S R G P D S'").unwrap());
+    assert_eq!(bhupali.vadi, madhya(Swara::Ga));
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use common::{cents_of, hold, SA_HZ};
+use raag_detection::classification::{
+    DiagnosticKind, Ornament, OrnamentDetector, OrnamentKind, OrnamentPattern, RaagDatabase, Transcriber,
+};
+use raag_detection::error::RaagError;
+use raag_detection::theory::{Note, Swara, TuningSystem};
+
+/// `frames` frames of the pitch swinging `depth` cents either side of `note`
+/// at `rate` cycles per second
+fn swing(note: &str, frames: usize, rate: f32, depth: f32) -> Vec<f32> {
+    let centre = cents_of(note);
+    (0..frames)
+        .map(|i| common::frequency(centre + depth * (2.0 * std::f32::consts::PI * rate * i as f32 / 100.0).sin()))
+        .collect()
+}
+
+fn detect(frequencies: Vec<f32>) -> Vec<Ornament> {
+    let contour = common::pitch_stream(frequencies);
+    let tuning = TuningSystem::default();
+    let transcription = Transcriber::default().transcribe(&contour, SA_HZ, &tuning);
+    OrnamentDetector::default().detect(&contour, SA_HZ, &transcription, &tuning)
+}
+
+fn note(text: &str) -> Note {
+    text.parse().unwrap()
+}
+
+#[test]
+fn slow_glide_between_notes_is_a_meend() {
+    let (from, to) = (cents_of("S"), cents_of("G"));
+    let glide: Vec<f32> = (1..30).map(|i| common::frequency(from + (to - from) * i as f32 / 30.0)).collect();
+    let ornaments = detect([hold("S", 40), glide, hold("G", 40)].concat());
+
+    assert_eq!(ornaments.len(), 1, "{:?}", ornaments);
+    assert_eq!(ornaments[0].kind, OrnamentKind::Meend { from: note("S"), to: note("G") });
+    assert!(ornaments[0].duration() > 0.15 && ornaments[0].duration() < 0.35);
+}
+
+#[test]
+fn plain_steps_have_no_ornaments() {
+    assert!(detect([hold("S", 40), hold("R", 40), hold("G", 40)].concat()).is_empty());
+}
+
+#[test]
+fn brief_touch_of_a_higher_note_is_a_kan() {
+    let ornaments = detect([hold("S", 40), hold("m", 6), hold("G", 40)].concat());
+
+    assert_eq!(ornaments.len(), 1, "{:?}", ornaments);
+    assert_eq!(ornaments[0].kind, OrnamentKind::Kan { grace: note("m"), note: note("G") });
+    assert!((ornaments[0].onset - 0.4).abs() < 1e-6 && (ornaments[0].offset - 0.46).abs() < 1e-6);
+}
+
+#[test]
+fn measures_rate_and_depth_of_an_andolan() {
+    let ornaments = detect([hold("S", 40), swing("g", 200, 1.5, 40.0)].concat());
+
+    let [Ornament {
+        kind: OrnamentKind::Oscillation {
+            note: swara,
+            rate_hz,
+            depth_cents,
+        },
+        ..
+    }] = ornaments[..]
+    else {
+        panic!("expected one oscillation, got {:?}", ornaments);
+    };
+    assert_eq!(swara, note("g"));
+    assert!((rate_hz - 1.5).abs() < 0.1, "rate {}", rate_hz);
+    assert!((depth_cents - 80.0).abs() < 5.0, "depth {}", depth_cents);
+}
+
+#[test]
+fn andolan_patterns_leave_out_fast_vibrato() {
+    let andolan = OrnamentPattern::Oscillation {
+        swara: Swara::KomalRe,
+        max_rate_hz: Some(3.0),
+    };
+    let slow = detect(swing("r", 200, 1.5, 40.0));
+    let fast = detect(swing("r", 200, 6.0, 20.0));
+
+    assert!(slow.iter().any(|ornament| andolan.matches(ornament)), "{:?}", slow);
+    assert!(!fast.is_empty() && !fast.iter().any(|ornament| andolan.matches(ornament)), "{:?}", fast);
+}
+
+#[test]
+fn raag_definitions_list_ornaments_and_are_checked() {
+    let bhairav = RaagDatabase::new().find_raag("Bhairav").cloned().unwrap();
+    assert!(bhairav.ornaments.contains(&OrnamentPattern::Oscillation {
+        swara: Swara::KomalRe,
+        max_rate_hz: Some(3.0),
+    }));
+
+    let definition = |ornaments: &str| {
+        format!(
+            r#"
+            [[raags]]
+            name = "Test"
+            thaat = "Bilawal"
+            aroha = "S R G m P D N S'"
+            avaroha = "S' N D P m G R S"
+            vadi = "G"
+            samvadi = "N"
+            ornaments = [{}]
+            "#,
+            ornaments
+        )
+    };
+
+    let ornaments = r#"{ kind = "meend", from = "m", to = "G" }, { kind = "oscillation", swara = "r" }"#;
+    let database = RaagDatabase::from_toml_str(&definition(ornaments)).unwrap();
+    let kinds: Vec<DiagnosticKind> = database.validate().into_iter().map(|d| d.kind).collect();
+    assert_eq!(kinds.len(), 1, "{:?}", kinds);
+    assert!(matches!(kinds[0], DiagnosticKind::SwaraNotInScale { .. }));
+
+    let Err(RaagError::RaagValidation(diagnostics)) =
+        RaagDatabase::from_toml_str(&definition(r#"{ kind = "kan", grace = "G", swara = "G" }"#))
+    else {
+        panic!("a kan onto its own swara should be rejected");
+    };
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::InvalidOrnament(0));
+}
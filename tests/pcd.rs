@@ -0,0 +1,148 @@
+use raag_detection::classification::{DistanceMetric, PitchClassDistribution, RaagDatabase};
+use raag_detection::theory::TuningSystem;
+
+const METRICS: [DistanceMetric; 3] =
+    [DistanceMetric::KlDivergence, DistanceMetric::Bhattacharyya, DistanceMetric::Cosine];
+
+/// Ratio to Sa of the equal-tempered swara `semitones` above it
+fn semitone(semitones: i32) -> f32 {
+    2f32.powf(semitones as f32 / 12.0)
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-4
+}
+
+#[test]
+fn scale_degrees_fold_into_one_octave_with_unit_mass() {
+    // Sa, taar Sa and Pa twice; zero and negative ratios are ignored
+    let pcd = PitchClassDistribution::from_scale_degrees(&[1.0, 2.0, 1.5, 1.5, 0.0, -1.0], 12);
+
+    assert_eq!(pcd.bins().len(), 12);
+    assert!(close(pcd.bins().iter().sum(), 1.0));
+    assert!(close(pcd.mass_at(1.0), 0.5));
+    assert!(close(pcd.mass_at(0.5), 0.5));
+    assert!(close(pcd.mass_at(1.5), 0.5));
+    assert!(pcd.mass_at(semitone(4)) < 1e-4);
+    assert_eq!(pcd.mass_at(0.0), 0.0);
+}
+
+#[test]
+fn weighted_cents_wrap_around_the_octave() {
+    let pcd = PitchClassDistribution::from_weighted_cents([(-100.0, 1.0), (1100.0, 1.0), (2300.0, 2.0)], 12);
+    assert!(close(pcd.mass_at(semitone(11)), 1.0));
+
+    let weighted = PitchClassDistribution::from_weighted_cents([(0.0, 3.0), (700.0, 1.0)], 12);
+    assert!(close(weighted.mass_at(1.0), 0.75));
+    assert!(close(weighted.mass_at(semitone(7)), 0.25));
+}
+
+#[test]
+fn fine_resolutions_spread_each_note_over_its_neighbours() {
+    // 10-cent bins: a note at Sa spreads symmetrically and peaks on its own bin
+    let pcd = PitchClassDistribution::from_scale_degrees(&[1.0], 120);
+    let bins = pcd.bins();
+    assert!(close(bins.iter().sum(), 1.0));
+    assert!(bins[0] > bins[1] && bins[1] > bins[2] && bins[2] > 0.0);
+    assert!(close(bins[1], bins[119]) && close(bins[2], bins[118]));
+    assert!(bins[60] < 1e-6);
+}
+
+#[test]
+fn no_data_gives_an_empty_distribution() {
+    let empty = PitchClassDistribution::from_scale_degrees(&[0.0, -2.0], 12);
+    assert!(empty.is_empty());
+    assert!(!PitchClassDistribution::from_scale_degrees(&[1.0], 12).is_empty());
+    assert_eq!(PitchClassDistribution::from_scale_degrees(&[1.0], 0).bins(), &[1.0]);
+}
+
+#[test]
+fn every_metric_is_zero_for_identical_distributions_and_orders_templates_by_overlap() {
+    let observed = PitchClassDistribution::from_weighted_cents([(0.0, 2.0), (400.0, 1.0), (700.0, 1.0)], 12);
+    let near = PitchClassDistribution::from_weighted_cents([(0.0, 1.0), (400.0, 1.0), (700.0, 1.0)], 12);
+    let far = PitchClassDistribution::from_weighted_cents([(0.0, 1.0), (300.0, 1.0), (800.0, 1.0)], 12);
+    let disjoint = PitchClassDistribution::from_weighted_cents([(100.0, 1.0), (600.0, 1.0)], 12);
+
+    for metric in METRICS {
+        let same = observed.distance(&observed, metric);
+        let (to_near, to_far, to_disjoint) =
+            (observed.distance(&near, metric), observed.distance(&far, metric), observed.distance(&disjoint, metric));
+        assert!(same.abs() < 1e-4, "{:?}: {}", metric, same);
+        assert!(same < to_near && to_near < to_far && to_far < to_disjoint, "{:?}", metric);
+    }
+}
+
+#[test]
+fn each_metric_matches_its_formula() {
+    let p = PitchClassDistribution::from_weighted_cents([(0.0, 1.0), (700.0, 1.0)], 12);
+    let q = PitchClassDistribution::from_weighted_cents([(0.0, 3.0), (700.0, 1.0)], 12);
+
+    let kl = 0.5 * (0.5f32 / 0.75).ln() + 0.5 * (0.5f32 / 0.25).ln();
+    let bhattacharyya = -((0.5f32 * 0.75).sqrt() + (0.5f32 * 0.25).sqrt()).ln();
+    let cosine = 1.0 - 0.5 / (0.5f32.sqrt() * 0.625f32.sqrt());
+    assert!(close(p.distance(&q, DistanceMetric::KlDivergence), kl));
+    assert!(close(p.distance(&q, DistanceMetric::Bhattacharyya), bhattacharyya));
+    assert!(close(p.distance(&q, DistanceMetric::Cosine), cosine));
+
+    // An empty template floors KL and Bhattacharyya instead of going infinite,
+    // and leaves cosine at its maximum
+    let empty = PitchClassDistribution::from_scale_degrees(&[], 12);
+    for metric in METRICS {
+        assert!(p.distance(&empty, metric).is_finite(), "{:?}", metric);
+    }
+    assert_eq!(p.distance(&empty, DistanceMetric::Cosine), 1.0);
+}
+
+fn raag(extra: &str) -> RaagDatabase {
+    let json = format!(
+        r#"{{ "raags": [{{ "name": "Test", "thaat": "Kalyan", "aroha": "S R G P D S'",
+            "avaroha": "S' D P G R S", "vadi": "G", "samvadi": "D" {} }}] }}"#,
+        extra
+    );
+    RaagDatabase::from_json_str(&json).unwrap()
+}
+
+#[test]
+fn templates_weight_vadi_over_samvadi_over_other_scale_notes() {
+    let database = raag("");
+    let template = PitchClassDistribution::from_raag(&database.get_raags()[0], 12, &TuningSystem::EqualTemperament);
+    let mass = |semitones| template.mass_at(semitone(semitones));
+
+    // One unit per scale note, however often it appears, plus 1 for the vadi and 0.5 for the samvadi
+    let total = 5.0 + 1.0 + 0.5;
+    assert!(close(mass(4), 2.0 / total), "vadi {}", mass(4));
+    assert!(close(mass(9), 1.5 / total), "samvadi {}", mass(9));
+    for other in [0, 2, 7] {
+        assert!(close(mass(other), 1.0 / total), "{}: {}", other, mass(other));
+    }
+    for outside in [1, 3, 5, 6, 8, 10, 11] {
+        assert!(mass(outside) < 1e-4, "{}: {}", outside, mass(outside));
+    }
+}
+
+#[test]
+fn characteristic_phrases_add_weight_to_their_notes() {
+    let plain = raag("");
+    let with_phrase = raag(r#", "characteristic_phrases": [{ "notes": "R P", "weight": 0.8 }]"#);
+    let tuning = TuningSystem::EqualTemperament;
+    let before = PitchClassDistribution::from_raag(&plain.get_raags()[0], 12, &tuning);
+    let after = PitchClassDistribution::from_raag(&with_phrase.get_raags()[0], 12, &tuning);
+
+    for phrase_note in [2, 7] {
+        assert!(after.mass_at(semitone(phrase_note)) > before.mass_at(semitone(phrase_note)));
+    }
+    assert!(after.mass_at(1.0) < before.mass_at(1.0));
+    assert!(after.mass_at(semitone(4)) > after.mass_at(semitone(2)), "the vadi still outweighs phrase notes");
+}
+
+#[test]
+fn templates_place_notes_by_the_tuning_and_the_raags_intonation() {
+    let database = raag(r#", "intonation": { "G": 370 }"#);
+    let tuned = PitchClassDistribution::from_raag(&database.get_raags()[0], 120, &TuningSystem::JustIntonation);
+    let bins = tuned.bins();
+
+    // Pa at 702 cents (bin 70) under just intonation; Ga moved to 370 cents
+    assert!(bins[70] > bins[69] && bins[70] > bins[71]);
+    assert!(bins[37] > bins[36] && bins[37] > bins[38]);
+    assert!(bins[39] < bins[37]);
+}
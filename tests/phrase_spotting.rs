@@ -0,0 +1,59 @@
+mod common;
+
+use common::SA_HZ;
+use raag_detection::classification::{PhraseMatch, PhraseSpotter, RaagDatabase, Transcriber};
+use raag_detection::theory::TuningSystem;
+
+/// Matches of Bhairavi's phrase `d m g r S` (its first characteristic phrase)
+fn descending_phrase_matches(melody: &[(&str, usize)]) -> Vec<PhraseMatch> {
+    let database = RaagDatabase::new();
+    let bhairavi = database.get_raag("Bhairavi").unwrap();
+    let tuning = TuningSystem::default();
+    let transcription = Transcriber::default().transcribe(&common::melody(melody), SA_HZ, &tuning);
+    PhraseSpotter::default()
+        .spot(bhairavi, &tuning, &transcription.notes)
+        .into_iter()
+        .filter(|found| found.phrase == 0)
+        .collect()
+}
+
+#[test]
+fn finds_a_phrase_at_any_tempo() {
+    let melody = [("P", 20), ("d", 60), ("m", 8), ("g", 25), ("r", 5), ("S", 80), ("P", 20)];
+    let found = descending_phrase_matches(&melody);
+    assert_eq!(found.len(), 1, "{:?}", found);
+    assert!(found[0].score > 0.95);
+    assert!((found[0].start - 0.2).abs() < 0.01);
+    assert!((found[0].end - 1.98).abs() < 0.01);
+}
+
+#[test]
+fn tolerates_an_ornament_between_phrase_notes() {
+    // A short touch of Pa between komal Dha and Ma
+    let melody = [("d", 30), ("P", 4), ("m", 30), ("g", 30), ("r", 30), ("S", 30)];
+    let found = descending_phrase_matches(&melody);
+    assert_eq!(found.len(), 1, "{:?}", found);
+    assert!(found[0].score >= PhraseSpotter::default().threshold && found[0].score < 1.0);
+}
+
+#[test]
+fn finds_a_phrase_in_another_octave() {
+    let melody = [("d'", 30), ("m'", 30), ("g'", 30), ("r'", 30), ("S'", 30)];
+    assert_eq!(descending_phrase_matches(&melody).len(), 1);
+}
+
+#[test]
+fn finds_every_occurrence() {
+    let phrase = [("d", 30), ("m", 30), ("g", 30), ("r", 30), ("S", 30)];
+    let melody: Vec<(&str, usize)> = phrase.iter().chain(&[("P", 30)]).chain(&phrase).copied().collect();
+    let found = descending_phrase_matches(&melody);
+    assert_eq!(found.len(), 2, "{:?}", found);
+    assert!(found[0].end < found[1].start);
+}
+
+#[test]
+fn rejects_a_phrase_with_a_wrong_swara() {
+    // Tivra Ma belongs to Yaman-like raags, not Bhairavi
+    let melody = [("d", 30), ("M", 30), ("g", 30), ("R", 30), ("S", 30)];
+    assert!(descending_phrase_matches(&melody).is_empty());
+}
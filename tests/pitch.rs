@@ -0,0 +1,101 @@
+mod common;
+
+use raag_detection::features::{FrameConfig, PitchAlgorithm, PitchExtractor, PitchFrame};
+
+const SAMPLE_RATE: u32 = 22050;
+
+/// One second of a tone at `frequency` with the given harmonic amplitudes,
+/// starting from the fundamental
+fn tone(frequency: f32, harmonics: &[f32], sample_rate: u32) -> Vec<f32> {
+    let mut samples = vec![0.0; sample_rate as usize];
+    for (k, amplitude) in harmonics.iter().enumerate() {
+        let partial = common::sine(frequency * (k + 1) as f32, sample_rate, 1.0);
+        for (sample, value) in samples.iter_mut().zip(partial) {
+            *sample += 0.3 * amplitude * value;
+        }
+    }
+    samples
+}
+
+/// Frames away from the edges, where the window is half padding
+fn inner(frames: &[PitchFrame]) -> &[PitchFrame] {
+    &frames[4..frames.len() - 4]
+}
+
+fn cents_off(frame: &PitchFrame, frequency: f32) -> f32 {
+    (1200.0 * (frame.frequency / frequency).log2()).abs()
+}
+
+#[test]
+fn tracks_sines_within_a_few_cents() {
+    for algorithm in [PitchAlgorithm::Yin, PitchAlgorithm::PYin] {
+        let extractor = PitchExtractor::with_algorithm(SAMPLE_RATE, algorithm);
+        for frequency in [110.0, 220.0, 440.0, 700.0] {
+            let frames = extractor.extract_f0(&tone(frequency, &[1.0], SAMPLE_RATE));
+            for frame in inner(&frames) {
+                assert!(frame.is_voiced() && frame.voicing > 0.5, "{:?} at {} Hz: {:?}", algorithm, frequency, frame);
+                assert!(cents_off(frame, frequency) < 5.0, "{:?} at {} Hz: {:?}", algorithm, frequency, frame);
+            }
+        }
+    }
+}
+
+#[test]
+fn silence_stays_unvoiced() {
+    for algorithm in [PitchAlgorithm::Yin, PitchAlgorithm::PYin] {
+        let extractor = PitchExtractor::with_algorithm(SAMPLE_RATE, algorithm);
+        let frames = extractor.extract_f0(&vec![0.0; SAMPLE_RATE as usize]);
+        assert_eq!(frames.len(), 44);
+        assert!(frames.iter().all(|frame| !frame.is_voiced()), "{:?}", algorithm);
+    }
+}
+
+#[test]
+fn harmonic_rich_tones_give_no_octave_errors() {
+    // A sawtooth-like voice, one whose second harmonic outweighs the
+    // fundamental, and one with most energy on the upper partials
+    let spectra: [&[f32]; 3] = [
+        &[1.0, 0.5, 0.33, 0.25, 0.2, 0.17, 0.14, 0.12],
+        &[0.4, 1.0, 0.3, 0.5, 0.1],
+        &[0.2, 0.6, 1.0, 0.8, 0.6, 0.4],
+    ];
+    for algorithm in [PitchAlgorithm::Yin, PitchAlgorithm::PYin] {
+        let extractor = PitchExtractor::with_algorithm(SAMPLE_RATE, algorithm);
+        for frequency in [130.0, 196.0, 294.0] {
+            for harmonics in spectra {
+                let frames = extractor.extract_f0(&tone(frequency, harmonics, SAMPLE_RATE));
+                for frame in inner(&frames) {
+                    assert!(
+                        cents_off(frame, frequency) < 10.0,
+                        "{:?} at {} Hz with {:?}: {:?}",
+                        algorithm,
+                        frequency,
+                        harmonics,
+                        frame
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn tracks_low_notes_at_high_sample_rates() {
+    let extractor = PitchExtractor::new(96000);
+    assert!(extractor.frame_config().window_size >= 2 * 96000 / 80);
+
+    let frames = extractor.extract_f0(&tone(90.0, &[1.0, 0.5], 96000));
+    for frame in inner(&frames) {
+        assert!(cents_off(frame, 90.0) < 5.0, "{:?}", frame);
+    }
+}
+
+#[test]
+fn short_windows_still_track_notes_they_can_hold() {
+    // 2048 samples at 96 kHz hold two periods of 94 Hz but not of 80 Hz
+    let extractor = PitchExtractor::new(96000).with_frame_config(FrameConfig::new(2048, 512));
+    let frames = extractor.extract_f0(&tone(300.0, &[1.0], 96000));
+    for frame in inner(&frames) {
+        assert!(cents_off(frame, 300.0) < 5.0, "{:?}", frame);
+    }
+}
@@ -0,0 +1,209 @@
+use raag_detection::classification::{DistanceMetric, Note, PitchClassDistribution, Raag, RaagDatabase, THAATS};
+use raag_detection::theory::{Swara, TuningSystem};
+
+const COMMON_RAAGS: [&str; 20] = [
+    "Yaman", "Bhairavi", "Bhimpalasi", "Bageshree", "Desh", "Des", "Khamaj", "Bhupali", "Durga", "Malkauns",
+    "Darbari", "Todi", "Marwa", "Puriya", "Shree", "Bihag", "Kedar", "Bhairav", "Kafi", "Asavari",
+];
+
+fn catalogue() -> RaagDatabase {
+    RaagDatabase::new()
+}
+
+fn pitch_class(note: &Note) -> usize {
+    note.swara.index()
+}
+
+/// Pitch classes used by the aroha and avaroha
+fn scale_pitch_classes(raag: &Raag) -> Vec<usize> {
+    let mut classes: Vec<usize> = raag
+        .aroha
+        .iter()
+        .chain(&raag.avaroha)
+        .map(pitch_class)
+        .collect();
+    classes.sort_unstable();
+    classes.dedup();
+    classes
+}
+
+#[test]
+fn passes_validation() {
+    let diagnostics = catalogue().validate();
+    assert!(diagnostics.is_empty(), "{:#?}", diagnostics);
+}
+
+#[test]
+fn covers_all_ten_thaats() {
+    let database = catalogue();
+    for thaat in THAATS {
+        assert!(
+            database.get_raags().iter().any(|raag| raag.thaat == thaat),
+            "no raag in thaat {}",
+            thaat
+        );
+    }
+}
+
+#[test]
+fn every_thaat_is_known() {
+    for raag in catalogue().get_raags() {
+        assert!(THAATS.contains(&raag.thaat.as_str()), "{}: unknown thaat {}", raag.name, raag.thaat);
+    }
+}
+
+#[test]
+fn contains_commonly_performed_raags() {
+    let database = catalogue();
+    for name in COMMON_RAAGS {
+        assert!(database.find_raag(name).is_some(), "missing raag {}", name);
+    }
+}
+
+#[test]
+fn names_and_aliases_are_unique() {
+    let database = catalogue();
+    let mut names: Vec<String> = database
+        .get_raags()
+        .iter()
+        .flat_map(|raag| std::iter::once(&raag.name).chain(&raag.aliases))
+        .map(|name| name.to_lowercase())
+        .collect();
+    let count = names.len();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), count, "duplicate raag names or aliases");
+}
+
+#[test]
+fn every_raag_is_complete() {
+    for raag in catalogue().get_raags() {
+        assert!(raag.aroha.len() >= 5, "{}: aroha too short", raag.name);
+        assert!(raag.avaroha.len() >= 5, "{}: avaroha too short", raag.name);
+        assert!(!raag.characteristic_phrases.is_empty(), "{}: no pakad phrases", raag.name);
+        assert_ne!(
+            pitch_class(&raag.vadi),
+            pitch_class(&raag.samvadi),
+            "{}: vadi and samvadi are the same note",
+            raag.name
+        );
+    }
+}
+
+#[test]
+fn aroha_ascends_and_avaroha_descends() {
+    for raag in catalogue().get_raags() {
+        for pair in raag.aroha.windows(2) {
+            assert!(
+                pair[1].semitones() > pair[0].semitones(),
+                "{}: aroha goes from {} down to {}",
+                raag.name,
+                pair[0],
+                pair[1]
+            );
+        }
+        for pair in raag.avaroha.windows(2) {
+            assert!(
+                pair[1].semitones() < pair[0].semitones(),
+                "{}: avaroha goes from {} up to {}",
+                raag.name,
+                pair[0],
+                pair[1]
+            );
+        }
+    }
+}
+
+#[test]
+fn scales_start_and_end_on_sa() {
+    for raag in catalogue().get_raags() {
+        let is_sa = |note: &Note| note.swara == Swara::Sa;
+        assert!(
+            raag.aroha.iter().any(is_sa) && raag.aroha.last().is_some_and(is_sa),
+            "{}: aroha does not reach Sa",
+            raag.name
+        );
+        assert!(raag.avaroha.last().is_some_and(is_sa), "{}: avaroha does not end on Sa", raag.name);
+    }
+}
+
+#[test]
+fn vadi_samvadi_and_phrases_use_scale_notes() {
+    for raag in catalogue().get_raags() {
+        let scale = scale_pitch_classes(raag);
+        for note in [&raag.vadi, &raag.samvadi] {
+            assert!(scale.contains(&pitch_class(note)), "{}: {} is not in the scale", raag.name, note);
+        }
+        for phrase in &raag.characteristic_phrases {
+            assert!(phrase.weight > 0.0 && phrase.weight <= 1.0, "{}: phrase weight {}", raag.name, phrase.weight);
+            for note in &phrase.notes {
+                assert!(
+                    scale.contains(&pitch_class(note)),
+                    "{}: phrase note {} is not in the scale",
+                    raag.name,
+                    note
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn notes_survive_a_round_trip_through_their_notation() {
+    for raag in catalogue().get_raags() {
+        for note in raag.aroha.iter().chain(&raag.avaroha) {
+            assert_eq!(note.to_string().parse::<Note>().ok(), Some(*note), "{}: {}", raag.name, note);
+        }
+    }
+}
+
+#[test]
+fn definitions_are_distinct() {
+    // Raags may share a scale, but not also the same movement and important notes
+    let signature = |raag: &Raag| {
+        let classes = |notes: &[Note]| notes.iter().map(pitch_class).collect::<Vec<_>>();
+        (
+            classes(&raag.aroha),
+            classes(&raag.avaroha),
+            pitch_class(&raag.vadi),
+            pitch_class(&raag.samvadi),
+        )
+    };
+
+    let raags = catalogue().get_raags().to_vec();
+    for (i, raag) in raags.iter().enumerate() {
+        for other in &raags[i + 1..] {
+            assert_ne!(signature(raag), signature(other), "{} and {} are indistinguishable", raag.name, other.name);
+        }
+    }
+}
+
+#[test]
+fn scale_notes_identify_their_own_raag_family() {
+    // A recording that dwells on a raag's scale, vadi and samvadi should rank
+    // that raag at least among the raags sharing its notes
+    let database = catalogue();
+    for raag in database.get_raags() {
+        let tuning = TuningSystem::default();
+        let mut ratios: Vec<f32> = raag.aroha.iter().chain(&raag.avaroha).map(|note| note.ratio(&tuning)).collect();
+        ratios.extend(std::iter::repeat_n(raag.vadi.ratio(&tuning), 4));
+        ratios.extend(std::iter::repeat_n(raag.samvadi.ratio(&tuning), 2));
+        let observed = PitchClassDistribution::from_scale_degrees(&ratios, 12);
+
+        let template = |raag: &Raag| PitchClassDistribution::from_raag(raag, 12, &tuning);
+        let own = observed.distance(&template(raag), DistanceMetric::Bhattacharyya);
+        let scale = scale_pitch_classes(raag);
+        for other in database.get_raags().iter().filter(|other| scale_pitch_classes(other) != scale) {
+            let distance = observed.distance(&template(other), DistanceMetric::Bhattacharyya);
+            assert!(own < distance, "{} observation is closer to {}", raag.name, other.name);
+        }
+    }
+}
+
+#[test]
+fn darbari_sings_a_lower_komal_ga_than_asavari() {
+    let database = catalogue();
+    let tuning = TuningSystem::default();
+    let komal_ga = |name: &str| database.get_raag(name).unwrap().tuning(&tuning).cents(Swara::KomalGa);
+    assert!(komal_ga("Darbari") < komal_ga("Asavari") - 10.0);
+}
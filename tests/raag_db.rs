@@ -0,0 +1,137 @@
+mod common;
+
+use raag_detection::classification::RaagDatabase;
+use raag_detection::RaagError;
+use std::path::PathBuf;
+
+const BHUPALI_TOML: &str = r#"
+[[raags]]
+name = "Test Bhupali"
+aliases = ["Test Bhoop"]
+thaat = "Kalyan"
+aroha = "S R G P D S'"
+avaroha = "S' D P G R S"
+vadi = "G"
+samvadi = "D"
+characteristic_phrases = [{ notes = "G R S .D", weight = 0.8 }]
+
+[[raags]]
+name = "Test Durga"
+thaat = "Bilawal"
+aroha = ["S", "R", "m", "P", "D", "S'"]
+avaroha = ["S'", "D", "P", "m", "R", "S"]
+vadi = "m"
+samvadi = "S"
+"#;
+
+const BHUPALI_JSON: &str = r#"{ "raags": [
+    { "name": "Test Bhupali", "aliases": ["Test Bhoop"], "thaat": "Kalyan",
+      "aroha": "S R G P D S'", "avaroha": "S' D P G R S", "vadi": "G", "samvadi": "D",
+      "characteristic_phrases": [{ "notes": "G R S .D", "weight": 0.8 }] },
+    { "name": "Test Durga", "thaat": "Bilawal", "aroha": ["S", "R", "m", "P", "D", "S'"],
+      "avaroha": ["S'", "D", "P", "m", "R", "S"], "vadi": "m", "samvadi": "S" }
+] }"#;
+
+/// Write `contents` to a temp file with the given extension
+fn write(contents: &str, extension: &str) -> PathBuf {
+    let path = common::temp_path("raags", extension);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+fn load(contents: &str, extension: &str) -> raag_detection::Result<RaagDatabase> {
+    let path = write(contents, extension);
+    let database = RaagDatabase::from_path(&path);
+    std::fs::remove_file(path).ok();
+    database
+}
+
+fn as_json(database: &RaagDatabase) -> serde_json::Value {
+    serde_json::to_value(database.get_raags()).unwrap()
+}
+
+#[test]
+fn toml_and_json_files_load_the_same_raags() {
+    let toml = load(BHUPALI_TOML, "toml").unwrap();
+    let json = load(BHUPALI_JSON, "json").unwrap();
+
+    assert_eq!(toml.get_raags().len(), 2);
+    assert_eq!(as_json(&toml), as_json(&json));
+
+    let bhupali = toml.get_raag("test bhoop").unwrap();
+    assert_eq!(bhupali.name, "Test Bhupali");
+    assert_eq!(bhupali.characteristic_phrases[0].notes.len(), 4);
+    assert_eq!(bhupali.characteristic_phrases[0].weight, 0.8);
+}
+
+#[test]
+fn the_extension_picks_the_format_in_any_case() {
+    assert!(load(BHUPALI_TOML, "TOML").is_ok());
+    assert!(load(BHUPALI_JSON, "Json").is_ok());
+
+    // TOML read as JSON fails, and the error names the file
+    let path = write(BHUPALI_TOML, "json");
+    let result = RaagDatabase::from_path(&path);
+    std::fs::remove_file(&path).ok();
+    match result {
+        Err(RaagError::InvalidRaagDefinitions(message)) => {
+            assert!(message.starts_with(&path.display().to_string()), "{}", message)
+        }
+        other => panic!("{:?}", other.err()),
+    }
+}
+
+#[test]
+fn rejects_other_extensions_and_missing_files() {
+    for extension in ["yaml", ""] {
+        let result = load(BHUPALI_TOML, extension);
+        assert!(matches!(result, Err(RaagError::InvalidRaagDefinitions(_))), "{:?}", result.err());
+    }
+
+    let missing = RaagDatabase::from_path(common::temp_path("missing", "toml"));
+    assert!(matches!(missing, Err(RaagError::Io(_))), "{:?}", missing.err());
+}
+
+#[test]
+fn reports_malformed_toml_with_its_location() {
+    let broken = BHUPALI_TOML.replace("vadi = \"G\"", "vadi = ");
+    match load(&broken, "toml") {
+        Err(RaagError::InvalidRaagDefinitions(message)) => assert!(message.contains("line"), "{}", message),
+        other => panic!("{:?}", other.err()),
+    }
+
+    let missing_field = BHUPALI_TOML.replace("samvadi = \"D\"\n", "");
+    match load(&missing_field, "toml") {
+        Err(RaagError::InvalidRaagDefinitions(message)) => assert!(message.contains("samvadi"), "{}", message),
+        other => panic!("{:?}", other.err()),
+    }
+}
+
+#[test]
+fn merge_adds_new_raags_and_replaces_those_with_the_same_name() {
+    let mut database = RaagDatabase::new();
+    let bundled = database.get_raags().len();
+    let yaman_position = database.get_raags().iter().position(|raag| raag.name == "Yaman").unwrap();
+
+    let corrections = BHUPALI_TOML.replace("Test Bhupali", "Yaman").replace("\"Test Bhoop\"", "\"Test Iman\"");
+    database.merge(RaagDatabase::from_toml_str(&corrections).unwrap());
+
+    assert_eq!(database.get_raags().len(), bundled + 1);
+    assert_eq!(database.get_raags()[yaman_position].name, "Yaman");
+    let yaman = database.get_raag("Yaman").unwrap();
+    assert_eq!(yaman.aliases, ["Test Iman"]);
+    assert_eq!(yaman.vadi.to_string(), "G");
+    assert!(yaman.aroha.iter().all(|note| note.to_string() != "M"), "the bundled Yaman was replaced");
+    assert!(database.find_raag("test durga").is_some());
+    assert!(database.find_raag("Test Iman").is_some());
+}
+
+#[test]
+fn merging_into_an_empty_database_keeps_only_the_new_raags() {
+    let mut database = RaagDatabase::from_raags(Vec::new());
+    database.merge(RaagDatabase::from_json_str(BHUPALI_JSON).unwrap());
+
+    let names: Vec<&str> = database.get_raags().iter().map(|raag| raag.name.as_str()).collect();
+    assert_eq!(names, ["Test Bhupali", "Test Durga"]);
+    assert!(matches!(database.get_raag("Yaman"), Err(RaagError::UnknownRaag(name)) if name == "Yaman"));
+}
@@ -0,0 +1,143 @@
+use raag_detection::classification::{Diagnostic, DiagnosticKind, Note, NoteRole, RaagDatabase, Severity};
+use raag_detection::RaagError;
+use serde_json::{json, Value};
+
+/// A valid Bhupali-like definition with some fields replaced
+fn raag(overrides: Value) -> Value {
+    let mut raag = json!({
+        "name": "Test",
+        "thaat": "Kalyan",
+        "aroha": "S R G P D S'",
+        "avaroha": "S' D P G R S",
+        "vadi": "G",
+        "samvadi": "D",
+        "characteristic_phrases": [{ "notes": "G R S .D", "weight": 0.8 }]
+    });
+    for (key, value) in overrides.as_object().unwrap() {
+        raag[key] = value.clone();
+    }
+    raag
+}
+
+fn load(raags: Vec<Value>) -> raag_detection::Result<RaagDatabase> {
+    RaagDatabase::from_json_str(&json!({ "raags": raags }).to_string())
+}
+
+/// Diagnostics of definitions that fail to load
+fn errors(raags: Vec<Value>) -> Vec<Diagnostic> {
+    match load(raags) {
+        Err(RaagError::RaagValidation(diagnostics)) => diagnostics,
+        Err(other) => panic!("failed for another reason: {}", other),
+        Ok(_) => panic!("loaded invalid definitions"),
+    }
+}
+
+fn note(text: &str) -> Note {
+    text.parse().unwrap()
+}
+
+#[test]
+fn the_base_definition_is_valid() {
+    assert!(load(vec![raag(json!({}))]).unwrap().validate().is_empty());
+}
+
+#[test]
+fn rejects_an_unknown_thaat() {
+    let diagnostics = errors(vec![raag(json!({ "thaat": "Yamanish" }))]);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::UnknownThaat("Yamanish".to_string()));
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert!(diagnostics[0].to_string().starts_with("error: Test: unknown thaat Yamanish"));
+
+    // Other spellings and cases of a thaat are fine
+    let spellings = vec![raag(json!({ "thaat": "kalyan" })), raag(json!({ "name": "Other", "thaat": "Bilaval" }))];
+    assert!(load(spellings).is_ok());
+}
+
+#[test]
+fn rejects_a_vadi_or_samvadi_outside_the_scale() {
+    let diagnostics = errors(vec![raag(json!({ "vadi": "m", "samvadi": "N" }))]);
+    let kinds: Vec<&DiagnosticKind> = diagnostics.iter().map(|diagnostic| &diagnostic.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            &DiagnosticKind::SwaraNotInScale { role: NoteRole::Vadi, note: note("m") },
+            &DiagnosticKind::SwaraNotInScale { role: NoteRole::Samvadi, note: note("N") },
+        ]
+    );
+    assert!(diagnostics.iter().all(Diagnostic::is_error));
+}
+
+#[test]
+fn phrase_notes_outside_the_scale_only_warn() {
+    let phrase = json!({ "characteristic_phrases": [{ "notes": "G m G R", "weight": 0.5 }] });
+    let database = load(vec![raag(phrase)]).unwrap();
+    let diagnostics = database.validate();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(
+        diagnostics[0].kind,
+        DiagnosticKind::SwaraNotInScale { role: NoteRole::Phrase(0), note: note("m") }
+    );
+}
+
+#[test]
+fn rejects_negative_phrase_weights() {
+    let diagnostics = errors(vec![raag(json!({
+        "characteristic_phrases": [
+            { "notes": "G R S", "weight": -0.5 },
+            { "notes": "P D S'", "weight": 0.0 }
+        ]
+    }))]);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::InvalidPhraseWeight { phrase: 0, weight: -0.5 });
+}
+
+#[test]
+fn rejects_duplicate_names_and_aliases_ignoring_case() {
+    let diagnostics = errors(vec![raag(json!({})), raag(json!({ "name": "TEST" }))]);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::DuplicateName("TEST".to_string()));
+
+    let diagnostics = errors(vec![
+        raag(json!({ "aliases": ["Shared"] })),
+        raag(json!({ "name": "Other", "aliases": ["shared"] })),
+    ]);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].raag, "Other");
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::DuplicateName("shared".to_string()));
+}
+
+#[test]
+fn reports_every_problem_at_once() {
+    let diagnostics = errors(vec![raag(json!({ "thaat": "Nowhere", "aroha": [], "vadi": "n" }))]);
+    let kinds: Vec<&DiagnosticKind> = diagnostics.iter().map(|diagnostic| &diagnostic.kind).collect();
+    assert!(kinds.contains(&&DiagnosticKind::UnknownThaat("Nowhere".to_string())));
+    assert!(kinds.contains(&&DiagnosticKind::EmptyScale(NoteRole::Aroha)));
+    assert!(kinds.contains(&&DiagnosticKind::SwaraNotInScale { role: NoteRole::Vadi, note: note("n") }));
+
+    let message = RaagError::RaagValidation(diagnostics).to_string();
+    assert_eq!(message.lines().count(), 4, "{}", message);
+}
+
+#[test]
+fn merging_catches_names_that_clash_with_the_bundled_raags() {
+    // Kalyan is an alias of the bundled Yaman
+    let extra = load(vec![raag(json!({ "name": "Kalyan" }))]).unwrap();
+    match RaagDatabase::new().merge_validated(extra) {
+        Err(RaagError::RaagValidation(diagnostics)) => {
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(diagnostics[0].kind, DiagnosticKind::DuplicateName("Kalyan".to_string()));
+        }
+        other => panic!("{:?}", other.map(|database| database.get_raags().len())),
+    }
+
+    let alias = load(vec![raag(json!({ "aliases": ["Bhoop"] }))]).unwrap();
+    assert!(RaagDatabase::new().merge_validated(alias).is_err());
+
+    // Reusing a bundled name replaces that raag instead of clashing with it
+    let replacement = load(vec![raag(json!({ "name": "Bhupali" }))]).unwrap();
+    let merged = RaagDatabase::new().merge_validated(replacement).unwrap();
+    assert_eq!(merged.get_raags().len(), RaagDatabase::new().get_raags().len());
+    assert!(merged.get_raag("Bhupali").unwrap().aliases.is_empty());
+}
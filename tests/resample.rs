@@ -0,0 +1,58 @@
+mod common;
+
+use common::sine;
+use raag_detection::audio::AudioPreprocessor;
+use raag_detection::{DetectorConfig, RaagDetector, RaagError};
+
+/// Largest difference from `expected`, away from the edges where the filter
+/// runs out of input
+fn worst_error(samples: &[f32], expected: &[f32]) -> f32 {
+    let edge = samples.len() / 10;
+    samples[edge..samples.len() - edge]
+        .iter()
+        .zip(&expected[edge..])
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0, f32::max)
+}
+
+/// RMS level away from the edges
+fn rms(samples: &[f32]) -> f32 {
+    let edge = samples.len() / 10;
+    common::rms(&samples[edge..samples.len() - edge])
+}
+
+#[test]
+fn output_length_follows_the_rate_ratio() {
+    let input = vec![0.0; 44101];
+    assert_eq!(AudioPreprocessor::resample(&input, 44100, 22050).len(), 22051);
+    assert_eq!(AudioPreprocessor::resample(&input, 44100, 48000).len(), 48002);
+    assert_eq!(AudioPreprocessor::resample(&input[..1000], 96000, 22050).len(), 230);
+    assert_eq!(AudioPreprocessor::resample(&input, 44100, 44100), input);
+}
+
+#[test]
+fn passband_tones_survive_down_and_upsampling() {
+    for (from, to) in [(44100, 22050), (48000, 22050), (22050, 44100), (16000, 22050)] {
+        let resampled = AudioPreprocessor::resample(&sine(1000.0, from, 0.5), from, to);
+        let error = worst_error(&resampled, &sine(1000.0, to, 0.5));
+        assert!(error < 0.01, "{} -> {} Hz: off by {}", from, to, error);
+    }
+}
+
+#[test]
+fn content_above_the_new_nyquist_is_removed_not_aliased() {
+    // 15 kHz would fold down to 7.05 kHz at 22.05 kHz
+    let resampled = AudioPreprocessor::resample(&sine(15000.0, 44100, 0.5), 44100, 22050);
+    let level = 20.0 * rms(&resampled).log10();
+    assert!(level < -60.0, "alias at {:.1} dB", level);
+}
+
+#[test]
+fn a_zero_analysis_rate_is_rejected() {
+    let detector = RaagDetector::new(DetectorConfig {
+        analysis_rate: Some(0),
+        ..DetectorConfig::default()
+    });
+    let result = detector.detect_mono(&sine(220.0, 22050, 1.0), 22050);
+    assert!(matches!(result, Err(RaagError::InvalidSampleRate(0))));
+}
@@ -0,0 +1,163 @@
+mod common;
+
+use common::rms;
+use hound::WavReader;
+use raag_detection::audio::{AudioPreprocessor, SeparationConfig};
+use raag_detection::{DetectorConfig, RaagDetector};
+use std::f32::consts::PI;
+use std::process::Command;
+
+const SAMPLE_RATE: u32 = 22050;
+const DRONE_SA_HZ: f32 = 150.0;
+
+fn tone(frequency: f32, seconds: f32) -> Vec<f32> {
+    common::sine(frequency, SAMPLE_RATE, seconds)
+}
+
+/// Energy of `samples` at `frequency`, as the amplitude of a matching sine
+fn amplitude_at(samples: &[f32], frequency: f32) -> f32 {
+    let (mut re, mut im) = (0.0, 0.0);
+    for (i, x) in samples.iter().enumerate() {
+        let phase = 2.0 * PI * frequency * i as f32 / SAMPLE_RATE as f32;
+        re += x * phase.cos();
+        im += x * phase.sin();
+    }
+    2.0 * (re * re + im * im).sqrt() / samples.len() as f32
+}
+
+#[test]
+fn harmonic_part_drops_clicks_and_keeps_tones() {
+    let sine = tone(440.0, 2.0);
+    let mut mix: Vec<f32> = sine.iter().map(|x| 0.5 * x).collect();
+    let clicks: Vec<usize> = (0..mix.len()).step_by(SAMPLE_RATE as usize / 4).collect();
+    for &click in &clicks {
+        mix[click] += 1.0;
+    }
+
+    let harmonic = AudioPreprocessor::harmonic(&mix, &SeparationConfig::default());
+
+    assert_eq!(harmonic.len(), mix.len());
+    let middle = SAMPLE_RATE as usize / 2..3 * SAMPLE_RATE as usize / 2;
+    assert!((amplitude_at(&harmonic[middle.clone()], 440.0) - 0.5).abs() < 0.1);
+    // What is left of each click over the tone, which peaks at 0.5
+    let excess = |signal: &[f32], click: usize| {
+        let near = click.saturating_sub(64)..(click + 64).min(signal.len());
+        signal[near].iter().map(|x| x.abs()).fold(0.0, f32::max) - 0.5
+    };
+    for &click in clicks.iter().filter(|click| middle.contains(click)) {
+        let (before, after) = (excess(&mix, click), excess(&harmonic, click));
+        assert!(after < 0.1 * before, "click at {}: {} of {} left", click, after, before);
+    }
+}
+
+/// A drone on Sa, Pa and upper Sa throughout, and the drone with a melody
+/// note on Ga sung only in the second half
+fn drone_and_mix(seconds: f32) -> (Vec<f32>, Vec<f32>) {
+    let drone: Vec<f32> = [DRONE_SA_HZ / 2.0, DRONE_SA_HZ * 0.75, DRONE_SA_HZ]
+        .iter()
+        .map(|&frequency| tone(frequency, seconds))
+        .fold(vec![0.0; (seconds * SAMPLE_RATE as f32) as usize], |sum, part| {
+            sum.iter().zip(&part).map(|(a, b)| a + 0.2 * b).collect()
+        });
+    let half = drone.len() / 2;
+    let melody: Vec<f32> = tone(DRONE_SA_HZ * 1.25, seconds)
+        .iter()
+        .enumerate()
+        .map(|(i, x)| if i >= half { 0.3 * x } else { 0.0 })
+        .collect();
+    let mix = drone.iter().zip(&melody).map(|(a, b)| a + b).collect();
+    (drone, mix)
+}
+
+#[test]
+fn lead_removes_a_steady_drone_and_keeps_the_melody() {
+    let (drone, mix) = drone_and_mix(4.0);
+    let (half, ga) = (drone.len() / 2, DRONE_SA_HZ * 1.25);
+
+    let lead = AudioPreprocessor::lead(&mix, SAMPLE_RATE, DRONE_SA_HZ, &SeparationConfig::default());
+
+    let quarter = SAMPLE_RATE as usize / 4;
+    let pause = &lead[quarter..half - quarter];
+    let sung = &lead[half + quarter..lead.len() - quarter];
+    assert!(rms(pause) < 0.2 * rms(&drone[quarter..half - quarter]), "drone left: {}", rms(pause));
+    assert!(amplitude_at(sung, ga) > 0.2, "melody lost: {}", amplitude_at(sung, ga));
+    assert!(amplitude_at(sung, DRONE_SA_HZ) < 0.05);
+}
+
+#[test]
+fn separating_in_blocks_gives_the_same_signal() {
+    let (_, mix) = drone_and_mix(2.0);
+    let whole = SeparationConfig::default();
+    let blocks = SeparationConfig {
+        block_frames: 7,
+        ..whole
+    };
+    assert!(mix.len() / whole.hop_size > 10 * blocks.block_frames);
+
+    let lead = |config| AudioPreprocessor::lead(&mix, SAMPLE_RATE, DRONE_SA_HZ, config);
+    let pairs = [
+        (AudioPreprocessor::harmonic(&mix, &whole), AudioPreprocessor::harmonic(&mix, &blocks)),
+        (lead(&whole), lead(&blocks)),
+    ];
+    for (at_once, in_blocks) in pairs {
+        assert_eq!(at_once.len(), in_blocks.len());
+        let largest = at_once.iter().zip(&in_blocks).map(|(a, b)| (a - b).abs()).fold(0.0, f32::max);
+        assert!(largest < 1e-5, "{}", largest);
+    }
+}
+
+/// A second of drone alone, then Bhupali's notes sung over it, 0.4 s each
+fn recital() -> Vec<f32> {
+    let aroha = [1.0, 9.0 / 8.0, 5.0 / 4.0, 3.0 / 2.0, 5.0 / 3.0, 2.0];
+    let ratios = aroha.iter().chain(aroha.iter().rev().skip(1));
+    let mut melody = vec![0.0; SAMPLE_RATE as usize];
+    for ratio in ratios {
+        melody.extend(tone(DRONE_SA_HZ * ratio, 0.4).iter().map(|x| 0.3 * x));
+    }
+    let seconds = melody.len() as f32 / SAMPLE_RATE as f32;
+    let drone = [DRONE_SA_HZ / 2.0, DRONE_SA_HZ * 0.75, DRONE_SA_HZ].iter().map(|&frequency| tone(frequency, seconds));
+    drone.fold(melody, |sum, part| sum.iter().zip(&part).map(|(a, b)| a + 0.2 * b).collect())
+}
+
+#[test]
+fn detector_tracks_pitch_on_the_separated_lead() {
+    let recital = recital();
+    let separating = DetectorConfig {
+        separation: Some(SeparationConfig::default()),
+        ..DetectorConfig::default()
+    };
+
+    let detection = RaagDetector::new(separating).detect_mono(&recital, SAMPLE_RATE).unwrap();
+    let lead = detection.lead.as_ref().expect("separation keeps the lead");
+    assert_eq!((lead.len(), detection.analysis_rate), (recital.len(), SAMPLE_RATE));
+    let intro = SAMPLE_RATE as usize / 4..3 * SAMPLE_RATE as usize / 4;
+    assert!(rms(&lead[intro.clone()]) < 0.2 * rms(&recital[intro]), "drone left in the lead");
+    let error = 1200.0 * (detection.classification.tonic.frequency / DRONE_SA_HZ).log2();
+    assert!(error.abs() < 20.0, "tonic off by {} cents", error);
+
+    let plain = RaagDetector::default().detect_mono(&recital, SAMPLE_RATE).unwrap();
+    assert!(plain.lead.is_none());
+}
+
+#[test]
+fn cli_writes_the_lead_melody() {
+    let input = common::write_wav("recital", common::pcm16(1, SAMPLE_RATE), &recital());
+    let output = common::temp_path("lead", "wav");
+
+    let run = Command::new(env!("CARGO_BIN_EXE_raag-detection"))
+        .arg(&input)
+        .arg("--write-lead")
+        .arg(&output)
+        .output()
+        .unwrap();
+    assert!(run.status.success(), "{}", String::from_utf8_lossy(&run.stderr));
+    assert!(String::from_utf8_lossy(&run.stdout).contains("Wrote lead melody to"));
+
+    let written = WavReader::open(&output).unwrap();
+    let spec = written.spec();
+    assert_eq!((spec.channels, spec.sample_rate), (1, SAMPLE_RATE));
+    assert_eq!(written.len() as usize, recital().len());
+
+    std::fs::remove_file(input).unwrap();
+    std::fs::remove_file(output).unwrap();
+}
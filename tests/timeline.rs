@@ -0,0 +1,71 @@
+mod common;
+
+use raag_detection::audio::ReadOptions;
+use raag_detection::features::{format_timestamp, FeatureStream, FrameConfig, StreamInfo};
+use raag_detection::{DetectorConfig, RaagDetector};
+use std::f32::consts::PI;
+
+/// Ten frames, one every 0.1 s, centred from 2.0 s on
+fn stream() -> FeatureStream<usize> {
+    FeatureStream::new(StreamInfo::new(100, FrameConfig::new(20, 10), 2.0), (0..10).collect())
+}
+
+#[test]
+fn frames_are_found_by_time() {
+    let stream = stream();
+    assert_eq!(stream.times()[..3], [2.0, 2.1, 2.2]);
+
+    assert_eq!(stream.at_time(2.0), Some(&0));
+    assert_eq!(stream.at_time(2.34), Some(&3));
+    assert_eq!(stream.at_time(2.36), Some(&4));
+    assert_eq!(stream.at_time(2.94), Some(&9));
+    // Half a hop either side of the stream still belongs to its end frames
+    assert_eq!(stream.at_time(1.96), Some(&0));
+    assert_eq!(stream.at_time(1.9), None);
+    assert_eq!(stream.at_time(3.1), None);
+    assert_eq!(FeatureStream::<usize>::new(stream.info, Vec::new()).at_time(2.0), None);
+}
+
+#[test]
+fn slices_cover_frames_centred_in_a_half_open_range() {
+    let stream = stream();
+    assert_eq!(stream.slice_time(2.2, 2.5), &[2, 3, 4]);
+    assert_eq!(stream.slice_time(2.15, 2.25), &[2]);
+    assert_eq!(stream.slice_time(0.0, 2.15), &[0, 1]);
+    assert_eq!(stream.slice_time(2.75, 60.0), &[8, 9]);
+    assert!(stream.slice_time(2.5, 2.2).is_empty());
+    assert!(stream.slice_time(5.0, 6.0).is_empty());
+}
+
+#[test]
+fn timestamps_read_as_hours_minutes_and_seconds() {
+    assert_eq!(format_timestamp(754.5), "00:12:34.5");
+    assert_eq!(format_timestamp(3600.04), "01:00:00.0");
+    assert_eq!(format_timestamp(-1.0), "00:00:00.0");
+}
+
+#[test]
+fn stream_times_start_where_the_audio_was_read_from() {
+    let samples: Vec<f32> = (0..3 * 22050)
+        .map(|i| 0.5 * (2.0 * PI * 220.0 * i as f32 / 22050.0).sin())
+        .collect();
+    let path = common::write_wav("offset", common::pcm16(1, 22050), &samples);
+    let detector = RaagDetector::new(DetectorConfig {
+        read_options: ReadOptions {
+            offset: 1.0,
+            max_duration: None,
+        },
+        ..DetectorConfig::default()
+    });
+
+    let audio = detector.load(&path).unwrap();
+    std::fs::remove_file(path).ok();
+    assert_eq!(audio.start_time, 1.0);
+    let from_file = detector.detect(&audio).unwrap();
+    assert_eq!(from_file.features.pitch_contour.info.start_time, 1.0);
+    assert_eq!(from_file.features.chromagram.info.start_time, 1.0);
+
+    // A bare signal starts at zero, whatever the detector would read from a file
+    let bare = detector.detect_mono(&samples, 22050).unwrap();
+    assert_eq!(bare.features.pitch_contour.info.start_time, 0.0);
+}
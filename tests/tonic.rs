@@ -0,0 +1,179 @@
+mod common;
+
+use common::SA_HZ;
+use raag_detection::classification::{parse_tonic, TonicEstimate, TonicEstimator, TonicSource};
+use raag_detection::features::{PitchFrame, SpectralAnalyzer};
+use std::f32::consts::PI;
+
+const SAMPLE_RATE: u32 = 22050;
+
+/// A Bhupali-like melody that dwells on its vadi Ga longer than on any other
+/// note, Sa and Sa' included
+const VADI_HEAVY: [(&str, usize); 10] = [
+    ("S", 50),
+    ("R", 30),
+    ("G", 60),
+    ("P", 50),
+    ("D", 30),
+    ("S'", 40),
+    ("D", 20),
+    ("P", 30),
+    ("G", 40),
+    ("R", 30),
+];
+
+fn contour(melody: &[(&str, usize)]) -> Vec<PitchFrame> {
+    common::melody(melody).frames
+}
+
+fn cents_off(estimate: &TonicEstimate, frequency: f32) -> f32 {
+    (1200.0 * (estimate.frequency / frequency).log2()).abs()
+}
+
+/// A tanpura-like drone of mandra Pa, Sa and Sa' with a few harmonics each,
+/// under the melody sung as a plain tone
+fn drone_and_melody(melody: &[(&str, usize)]) -> Vec<f32> {
+    let voice: Vec<f32> = contour(melody)
+        .iter()
+        .flat_map(|frame| vec![frame.frequency; SAMPLE_RATE as usize / 100])
+        .collect();
+    let strings = [SA_HZ * 0.75, SA_HZ, SA_HZ * 2.0];
+
+    let mut phase = 0.0f32;
+    voice
+        .iter()
+        .enumerate()
+        .map(|(i, &frequency)| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let drone: f32 = strings
+                .iter()
+                .flat_map(|&string| (1..=4).map(move |harmonic| (string * harmonic as f32, 1.0 / harmonic as f32)))
+                .map(|(frequency, amplitude)| amplitude * (2.0 * PI * frequency * t).sin())
+                .sum();
+            phase += 2.0 * PI * frequency / SAMPLE_RATE as f32;
+            0.05 * drone + 0.3 * phase.sin()
+        })
+        .collect()
+}
+
+#[test]
+fn a_sa_and_pa_drone_fixes_sa_under_a_melody() {
+    // Ga outweighs everything in the melody, Sa included
+    let melody = [("G", 120), ("R", 40), ("G", 80), ("m", 40), ("G", 120), ("R", 40), ("S", 40)];
+    let spectrum = SpectralAnalyzer::new(SAMPLE_RATE, 8192).long_term_spectrum(&drone_and_melody(&melody));
+
+    let estimator = TonicEstimator::default();
+    let estimate = estimator.estimate(&contour(&melody), Some(&spectrum)).unwrap();
+    assert_eq!(estimate.source, TonicSource::PitchAndDrone);
+    assert!(cents_off(&estimate, SA_HZ) < 10.0, "{:?}", estimate);
+
+    // The melody alone points elsewhere
+    let melody_only = estimator.estimate(&contour(&melody), None).unwrap();
+    assert!(cents_off(&melody_only, SA_HZ) > 50.0, "{:?}", melody_only);
+}
+
+#[test]
+fn a_melody_heavy_on_its_vadi_still_gives_sa() {
+    let estimate = TonicEstimator::default().estimate(&contour(&VADI_HEAVY), None).unwrap();
+    assert_eq!(estimate.source, TonicSource::PitchHistogram);
+    assert!(cents_off(&estimate, SA_HZ) < 5.0, "{:?}", estimate);
+    assert!(estimate.confidence > 0.0 && estimate.confidence <= 1.0);
+}
+
+#[test]
+fn tracks_sa_between_the_semitones() {
+    for detune in [-40.0f32, 17.0, 45.0] {
+        let ratio = 2f32.powf(detune / 1200.0);
+        let frames: Vec<PitchFrame> = contour(&VADI_HEAVY)
+            .into_iter()
+            .map(|frame| PitchFrame { frequency: frame.frequency * ratio, ..frame })
+            .collect();
+        let estimate = TonicEstimator::default().estimate(&frames, None).unwrap();
+        assert!(cents_off(&estimate, SA_HZ * ratio) < 5.0, "{} cents: {:?}", detune, estimate);
+    }
+}
+
+#[test]
+fn places_sa_in_the_octave_that_was_sung() {
+    // The default range, 90-360 Hz, holds both 100 and 200 Hz
+    let upper = contour(&VADI_HEAVY);
+    let lower: Vec<PitchFrame> = upper
+        .iter()
+        .map(|frame| PitchFrame { frequency: frame.frequency / 2.0, ..*frame })
+        .collect();
+
+    let estimator = TonicEstimator::default();
+    assert!(cents_off(&estimator.estimate(&upper, None).unwrap(), SA_HZ) < 5.0);
+    assert!(cents_off(&estimator.estimate(&lower, None).unwrap(), SA_HZ / 2.0) < 5.0);
+
+    // A range one octave wide leaves a single choice, whatever was sung
+    let narrow = TonicEstimator {
+        min_frequency: 150.0,
+        max_frequency: 300.0,
+    };
+    assert!(cents_off(&narrow.estimate(&lower, None).unwrap(), SA_HZ) < 5.0);
+    let high = TonicEstimator {
+        min_frequency: 300.0,
+        max_frequency: 500.0,
+    };
+    assert!(cents_off(&high.estimate(&upper, None).unwrap(), 2.0 * SA_HZ) < 5.0);
+}
+
+#[test]
+fn unvoiced_contours_have_no_tonic() {
+    let silence = vec![PitchFrame { frequency: 0.0, voicing: 0.0 }; 100];
+    assert!(TonicEstimator::default().estimate(&silence, None).is_err());
+}
+
+fn parses_to(spec: &str, frequency: f32) -> bool {
+    parse_tonic(spec).is_ok_and(|parsed| (1200.0 * (parsed / frequency).log2()).abs() < 0.01)
+}
+
+#[test]
+fn parses_tonics_in_hz() {
+    for spec in ["138.6", "138.6Hz", "138.6hz", " 138.6 Hz "] {
+        assert!(parses_to(spec, 138.6), "{}", spec);
+    }
+    assert!(parses_to("146.8-10c", 146.8 * 2f32.powf(-10.0 / 1200.0)));
+    assert!(parses_to("146.8Hz+1200c", 293.6));
+}
+
+#[test]
+fn parses_note_names_in_octave_three_by_default() {
+    let c_sharp_3 = 440.0 * 2f32.powf(-20.0 / 12.0);
+    for spec in ["C#", "c#", "Db", "C#3", "Db3"] {
+        assert!(parses_to(spec, c_sharp_3), "{}", spec);
+    }
+    assert!(parses_to("A3", 220.0));
+    assert!(parses_to("A4", 440.0));
+    assert!(parses_to("F#2", 440.0 * 2f32.powf(-27.0 / 12.0)));
+}
+
+#[test]
+fn parses_cent_offsets_after_note_names() {
+    let c_sharp_3 = 440.0 * 2f32.powf(-20.0 / 12.0);
+    assert!(parses_to("C#+25c", c_sharp_3 * 2f32.powf(25.0 / 1200.0)));
+    assert!(parses_to("C#-1c", c_sharp_3 * 2f32.powf(-1.0 / 1200.0)));
+    assert!(parses_to("A3+100C", 440.0 * 2f32.powf(-11.0 / 12.0)));
+    assert!(parses_to("Db2-50c", c_sharp_3 / 2.0 * 2f32.powf(-50.0 / 1200.0)));
+}
+
+#[test]
+fn a_signed_number_without_c_is_an_octave_not_cents() {
+    // C# in octave -1 is 8.7 Hz, far below any tonic
+    let error = parse_tonic("C#-1").unwrap_err();
+    assert!(error.contains("out of range"), "{}", error);
+    assert!(parse_tonic("C#+25").is_err());
+}
+
+#[test]
+fn rejects_tonics_out_of_range_or_unreadable() {
+    for spec in ["10", "3000", "0", "-5", "C9", "C0", "A3+4800c"] {
+        let error = parse_tonic(spec).unwrap_err();
+        assert!(error.contains("out of range"), "{}: {}", spec, error);
+    }
+    for spec in ["", "H3", "Sa", "C#+x25c", "138.6kHz", "C##"] {
+        let error = parse_tonic(spec).unwrap_err();
+        assert!(error.starts_with("Invalid tonic"), "{}: {}", spec, error);
+    }
+}
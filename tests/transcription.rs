@@ -0,0 +1,56 @@
+mod common;
+
+use common::{hold, hold_off, SA_HZ};
+use raag_detection::classification::{Transcriber, Transcription};
+use raag_detection::theory::{Note, TuningSystem};
+
+fn transcribe(frequencies: Vec<f32>) -> Transcription {
+    Transcriber::default().transcribe(&common::pitch_stream(frequencies), SA_HZ, &TuningSystem::default())
+}
+
+#[test]
+fn transcribes_steady_notes_with_times() {
+    let transcription = transcribe([hold("S", 50), hold_off("R", 30, 8.0), hold_off("G", 40, -10.0)].concat());
+    assert_eq!(transcription.sargam(), "S R G");
+
+    let times: Vec<(f64, f64)> = transcription.notes.iter().map(|note| (note.onset, note.offset)).collect();
+    let expected = [(0.0, 0.5), (0.5, 0.8), (0.8, 1.2)];
+    for ((onset, offset), (expected_onset, expected_offset)) in times.into_iter().zip(expected) {
+        assert!((onset - expected_onset).abs() < 1e-6 && (offset - expected_offset).abs() < 1e-6);
+    }
+    assert!((transcription.notes[1].deviation(&TuningSystem::default()) - 8.0).abs() < 0.5);
+}
+
+#[test]
+fn writes_tivra_ma_so_it_parses_back() {
+    let melody = [hold("G", 30), hold("M", 30), hold("P", 30), hold("S'", 30)];
+    let transcription = transcribe(melody.concat());
+    assert_eq!(transcription.sargam(), "G m^ P S'");
+    let notes: Vec<Note> = transcription.notes.iter().map(|transcribed| transcribed.note).collect();
+    assert_eq!(Note::parse_sequence(&transcription.sargam()).unwrap(), notes);
+}
+
+#[test]
+fn glides_and_blips_are_not_notes_but_short_steady_notes_are() {
+    let glide: Vec<f32> = (0..40).map(|i| common::frequency(i as f32 * 10.0)).collect();
+    let transcription = transcribe([hold("S", 30), glide, hold("P", 5), hold("G", 30)].concat());
+    assert_eq!(transcription.sargam(), "S P G");
+
+    let blip = transcribe([hold("S", 30), hold("R", 3), hold("G", 30)].concat());
+    assert_eq!(blip.sargam(), "S G");
+}
+
+#[test]
+fn keeps_every_note_of_a_fast_run() {
+    let run: Vec<Vec<f32>> = ["S", "R", "G", "m", "P", "D", "N", "S'"].iter().map(|note| hold(note, 10)).collect();
+    assert_eq!(transcribe(run.concat()).sargam(), "S R G m P D N S'");
+}
+
+#[test]
+fn brief_gaps_do_not_split_a_note() {
+    let melody = [hold("P", 30), vec![0.0; 5], hold("P", 30), vec![0.0; 30], hold("P", 30)];
+    let transcription = transcribe(melody.concat());
+    let durations: Vec<f64> = transcription.notes.iter().map(|note| note.duration()).collect();
+    assert_eq!(transcription.sargam(), "P P");
+    assert!((durations[0] - 0.65).abs() < 1e-6 && (durations[1] - 0.3).abs() < 1e-6);
+}
@@ -0,0 +1,168 @@
+use raag_detection::theory::{Intonation, Shruti, Swara, TuningSystem};
+use std::collections::BTreeMap;
+
+const DARBARI_LIKE: &str = "0, 90, 204, 294, 386, 498, 590, 702, 792, 884, 996, 1088";
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 0.01
+}
+
+fn cents_of(numerator: f32, denominator: f32) -> f32 {
+    1200.0 * (numerator / denominator).log2()
+}
+
+#[test]
+fn named_tuning_systems_parse_in_any_case() {
+    for name in ["et", "12-TET", "Equal", "equal-temperament"] {
+        assert_eq!(name.parse(), Ok(TuningSystem::EqualTemperament), "{}", name);
+    }
+    for name in ["just", "JI", "Just-Intonation"] {
+        assert_eq!(name.parse(), Ok(TuningSystem::JustIntonation), "{}", name);
+    }
+    for name in ["shruti", " 22-Shruti "] {
+        assert_eq!(name.parse(), Ok(TuningSystem::shruti()), "{}", name);
+    }
+}
+
+#[test]
+fn a_custom_tuning_is_twelve_cent_values_from_sa() {
+    let tuning: TuningSystem = DARBARI_LIKE.parse().unwrap();
+    let expected = [0.0, 90.0, 204.0, 294.0, 386.0, 498.0, 590.0, 702.0, 792.0, 884.0, 996.0, 1088.0];
+    assert_eq!(tuning, TuningSystem::Custom(expected));
+    assert_eq!(tuning.cent_table(), expected);
+    assert_eq!(tuning.cents(Swara::KomalGa), 294.0);
+    assert!(close(tuning.ratio(Swara::Pa), 2f32.powf(702.0 / 1200.0)));
+    let equal = TuningSystem::Custom(std::array::from_fn(|i| 100.0 * i as f32));
+    assert_eq!("0,100,200,300,400,500,600,700,800,900,1000,1100".parse(), Ok(equal));
+}
+
+#[test]
+fn custom_tunings_need_exactly_twelve_numbers() {
+    let eleven = "0, 90, 204, 294, 386, 498, 590, 702, 792, 884, 996";
+    let error = eleven.parse::<TuningSystem>().unwrap_err();
+    assert!(error.contains("12 cent values, got 11"), "{}", error);
+
+    let thirteen = format!("{}, 1200", DARBARI_LIKE);
+    assert!(thirteen.parse::<TuningSystem>().unwrap_err().contains("got 13"));
+
+    for text in ["pythagorean", "0, 90, x, 294", "", "0;90;204"] {
+        let error = text.parse::<TuningSystem>().unwrap_err();
+        assert!(error.starts_with("Unknown tuning system"), "{}: {}", text, error);
+    }
+}
+
+#[test]
+fn custom_tunings_keep_each_swara_near_its_place() {
+    // Re at 260 cents is closer to komal Ga than to Re
+    let error = DARBARI_LIKE.replace("204", "260").parse::<TuningSystem>().unwrap_err();
+    assert!(error.contains("260") && error.contains("200"), "{}", error);
+
+    // Sa has to stay at Sa, and every value has to be a finite number
+    assert!(DARBARI_LIKE.replacen("0,", "60,", 1).parse::<TuningSystem>().is_err());
+    assert!(DARBARI_LIKE.replace("702", "NaN").parse::<TuningSystem>().is_err());
+    assert!(DARBARI_LIKE.replace("702", "inf").parse::<TuningSystem>().is_err());
+
+    // 50 cents either way is still the same swara
+    assert!(DARBARI_LIKE.replace("702", "650").parse::<TuningSystem>().is_ok());
+    assert!(DARBARI_LIKE.replace("702", "749").parse::<TuningSystem>().is_ok());
+}
+
+#[test]
+fn equal_and_just_tunings_place_the_swaras() {
+    for swara in Swara::ALL {
+        assert_eq!(TuningSystem::EqualTemperament.cents(swara), 100.0 * swara.index() as f32);
+    }
+    let just = TuningSystem::JustIntonation;
+    assert!(close(just.cents(Swara::Pa), cents_of(3.0, 2.0)));
+    assert!(close(just.cents(Swara::Ga), cents_of(5.0, 4.0)));
+    assert!(close(just.cents(Swara::KomalNi), cents_of(16.0, 9.0)));
+    assert!(close(just.ratio(Swara::Ma), 4.0 / 3.0));
+    assert_eq!(TuningSystem::default(), just);
+}
+
+#[test]
+fn the_22_shrutis_rise_through_the_octave_with_their_ratios() {
+    let cents: Vec<f32> = Shruti::ALL.iter().map(|shruti| shruti.cents()).collect();
+    assert_eq!(cents[0], 0.0);
+    assert!(cents.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(cents[21] < 1200.0);
+
+    let ratios = [
+        (Shruti::Tivra, 256.0, 243.0),
+        (Shruti::Chandovati, 9.0, 8.0),
+        (Shruti::Dayavati, 32.0, 27.0),
+        (Shruti::Raktika, 5.0, 4.0),
+        (Shruti::Raudri, 81.0, 64.0),
+        (Shruti::Marjani, 3.0, 2.0),
+        (Shruti::Ugra, 243.0, 128.0),
+    ];
+    for (shruti, numerator, denominator) in ratios {
+        assert!(close(shruti.ratio(), numerator / denominator), "{}", shruti);
+        assert!(close(shruti.cents(), cents_of(numerator, denominator)), "{}", shruti);
+    }
+}
+
+#[test]
+fn each_shruti_belongs_to_a_swara() {
+    for (swara, shruti) in Swara::ALL.iter().zip(Shruti::DEFAULTS) {
+        assert_eq!(shruti.swara(), *swara, "{}", shruti);
+    }
+    // Dayavati is a low komal Ga, Raudri a high shuddha Ga
+    assert_eq!(Shruti::Dayavati.swara(), Swara::KomalGa);
+    assert_eq!(Shruti::Raudri.swara(), Swara::Ga);
+
+    let shruti = TuningSystem::shruti();
+    for (swara, default) in Swara::ALL.iter().zip(Shruti::DEFAULTS) {
+        assert_eq!(shruti.cents(*swara), default.cents());
+    }
+}
+
+#[test]
+fn shruti_names_parse_in_any_case() {
+    for shruti in Shruti::ALL {
+        assert_eq!(shruti.name().to_uppercase().parse(), Ok(shruti));
+    }
+    assert!("Dayawati".parse::<Shruti>().is_err());
+}
+
+#[test]
+fn nearest_finds_the_swara_and_deviation_in_any_octave() {
+    let et = TuningSystem::EqualTemperament;
+    let (swara, deviation) = et.nearest(390.0);
+    assert_eq!(swara, Swara::Ga);
+    assert!(close(deviation, -10.0));
+
+    let octaves_away = [(-10.0, Swara::Sa, -10.0), (1195.0, Swara::Sa, -5.0), (3130.0, Swara::Pa, 30.0)];
+    for (cents, swara, deviation) in octaves_away {
+        let found = et.nearest(cents);
+        assert_eq!(found.0, swara, "{}", cents);
+        assert!(close(found.1, deviation), "{}: {:?}", cents, found);
+    }
+
+    // The same pitch is in tune under just intonation
+    let (swara, deviation) = TuningSystem::JustIntonation.nearest(cents_of(5.0, 4.0) - 1200.0);
+    assert_eq!(swara, Swara::Ga);
+    assert!(deviation.abs() < 0.01);
+
+    // A custom table moves the boundaries between swaras
+    let low_ga: TuningSystem = DARBARI_LIKE.replace("294", "260").parse().unwrap();
+    assert_eq!(low_ga.nearest(240.0).0, Swara::KomalGa);
+    assert_eq!(et.nearest(240.0).0, Swara::Re);
+}
+
+#[test]
+fn intonation_moves_only_the_listed_swaras() {
+    let mut darbari = BTreeMap::new();
+    darbari.insert(Swara::KomalGa, Intonation::Shruti(Shruti::Dayavati));
+    darbari.insert(Swara::KomalDha, Intonation::Cents(780.0));
+
+    let base = TuningSystem::JustIntonation;
+    let tuned = base.with_intonation(&darbari);
+    assert!(close(tuned.cents(Swara::KomalGa), cents_of(32.0, 27.0)));
+    assert_eq!(tuned.cents(Swara::KomalDha), 780.0);
+    for swara in Swara::ALL.into_iter().filter(|swara| !darbari.contains_key(swara)) {
+        assert_eq!(tuned.cents(swara), base.cents(swara), "{}", swara);
+    }
+
+    assert_eq!(base.with_intonation(&BTreeMap::new()), base);
+}
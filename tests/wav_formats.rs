@@ -0,0 +1,67 @@
+mod common;
+
+use hound::{SampleFormat, WavSpec};
+use raag_detection::audio::AudioReader;
+
+const SAMPLE_RATE: u32 = 22050;
+const AMPLITUDE: f32 = 0.5;
+
+/// One second of a half-scale 440 Hz sine, interleaved over `channels`
+fn sine(channels: u16) -> Vec<f32> {
+    common::sine(440.0, SAMPLE_RATE, 1.0)
+        .into_iter()
+        .flat_map(|value| std::iter::repeat_n(AMPLITUDE * value, channels as usize))
+        .collect()
+}
+
+fn assert_decodes(name: &str, channels: u16, bits_per_sample: u16, sample_format: SampleFormat) {
+    let spec = WavSpec {
+        channels,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample,
+        sample_format,
+    };
+    let expected = sine(channels);
+    let path = common::write_wav(name, spec, &expected);
+    let audio = AudioReader::from_file(&path).unwrap();
+    std::fs::remove_file(path).ok();
+
+    assert_eq!((audio.sample_rate, audio.channels, audio.frames()), (SAMPLE_RATE, channels, SAMPLE_RATE as usize));
+    // Every sample within one quantisation step of the original, so the
+    // scaling to [-1, 1] is right for the bit depth
+    let step = 1.0 / (1u64 << (bits_per_sample - 1)) as f32;
+    let worst = audio
+        .samples
+        .iter()
+        .zip(&expected)
+        .map(|(decoded, original)| (decoded - original).abs())
+        .fold(0.0f32, f32::max);
+    assert!(worst <= step, "{}: off by {}", name, worst);
+    let peak = audio.samples.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+    assert!((peak - AMPLITUDE).abs() < 0.01, "{}: peak {}", name, peak);
+}
+
+#[test]
+fn decodes_8_bit_int() {
+    assert_decodes("int8", 1, 8, SampleFormat::Int);
+}
+
+#[test]
+fn decodes_16_bit_int() {
+    assert_decodes("int16", 2, 16, SampleFormat::Int);
+}
+
+#[test]
+fn decodes_24_bit_int() {
+    assert_decodes("int24", 2, 24, SampleFormat::Int);
+}
+
+#[test]
+fn decodes_32_bit_int() {
+    assert_decodes("int32", 1, 32, SampleFormat::Int);
+}
+
+#[test]
+fn decodes_32_bit_float() {
+    assert_decodes("float32", 2, 32, SampleFormat::Float);
+}